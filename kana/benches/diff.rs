@@ -0,0 +1,65 @@
+#[macro_use]
+extern crate criterion;
+extern crate kana;
+
+use criterion::{black_box, Criterion};
+
+use kana::{tokenize, Syllable};
+
+// spell-checker: disable
+
+const WORD: &str = "ありがとう";
+
+const SENTENCE: &str =
+    "きょうはとてもいいてんきなので、ともだちといっしょにこうえんへさんぽにいきました。";
+
+/// Repeats `text` with a few typos in each copy.
+fn typos(text: &str, count: usize) -> String {
+    let mut out = String::new();
+    for _ in 0..count {
+        out.push_str(
+            &text
+                .replace("to", "ro")
+                .replace("shi", "si")
+                .replace("n", "nn"),
+        );
+    }
+    out
+}
+
+/// Splits the kana into syllables, so that only the diff is measured.
+fn syllables(text: &str) -> Vec<Syllable> {
+    tokenize(text, Default::default(), Default::default())
+}
+
+fn bench_diff(c: &mut Criterion) {
+    let source = syllables(WORD);
+    let romaji = kana::to_romaji(WORD, Default::default(), Default::default());
+    let input = typos(&romaji, 1);
+    c.bench_function("word", |b| {
+        b.iter(|| kana::diff(black_box(&source), &romaji))
+    });
+    c.bench_function("word typos", |b| {
+        b.iter(|| kana::diff(black_box(&source), &input))
+    });
+
+    let source = syllables(SENTENCE);
+    let romaji = kana::to_romaji(SENTENCE, Default::default(), Default::default());
+    let input = typos(&romaji, 1);
+    c.bench_function("sentence", |b| {
+        b.iter(|| kana::diff(black_box(&source), &romaji))
+    });
+    c.bench_function("sentence typos", |b| {
+        b.iter(|| kana::diff(black_box(&source), &input))
+    });
+
+    // About 200 syllables.
+    let source = syllables(&SENTENCE.repeat(5));
+    let input = typos(&romaji, 5);
+    c.bench_function("paragraph typos", |b| {
+        b.iter(|| kana::diff(black_box(&source), &input))
+    });
+}
+
+criterion_group!(benches, bench_diff);
+criterion_main!(benches);
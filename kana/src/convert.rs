@@ -0,0 +1,592 @@
+use std::collections::HashMap;
+
+use std::ops::Range;
+
+use normalize::normalize;
+use romaji::to_romaji;
+use split::{ConversionError, SyllableError};
+use tables::*;
+
+/// Converts romaji text to hiragana.
+///
+/// See [`to_katakana`] for details on the conversion.
+pub fn to_hiragana(input: &str) -> String {
+    to_kana(input, false).0
+}
+
+/// Same as `to_hiragana`, but fails on the first romaji that cannot
+/// be converted.
+pub fn try_to_hiragana(input: &str) -> Result<String, ConversionError> {
+    try_to_kana(input, false)
+}
+
+/// Converts romaji text to katakana.
+///
+/// The conversion is the reverse of [`to_romaji`](fn.to_romaji.html)
+/// and is based on the same translation tables. The romaji can be in
+/// any of the supported romanization systems:
+///
+/// - Doubled consonants generate a small tsu (e.g. `matte` and
+///   `kotchi`).
+/// - `n'`, `nn` and `n` not followed by a vowel or `y` generate `ン`,
+///   as does `m` before `b`, `m` and `p`.
+/// - Digraphs are generated from the small kana (e.g. `kya`, `fa`,
+///   `kwa`).
+/// - Spellings from modified Hepburn take precedence over the other
+///   systems. For katakana, this includes the digraphs common in
+///   loanwords, while for hiragana the single kana of any system
+///   have precedence (e.g. `ti` is `ティ` but `ち`).
+/// - A `-` and, for katakana only, a vowel repeating the previous
+///   syllable vowel generate the long bar `ー`.
+/// - Vowels with macron or circumflex (e.g. `ō` and `â`) generate the
+///   long bar `ー` in katakana or are spelled with the vowel kana in
+///   hiragana (e.g. `tōkyō` is `とうきょう`).
+/// - Historical and rarely used kana (e.g. `ゐ`, `ヷ`, `ヶ` and `ゎ`)
+///   are never generated.
+///
+/// The input is normalized with [`normalize`](fn.normalize.html), so
+/// full-width romaji is also accepted.
+///
+/// Any text that cannot be converted is kept as is in the output, so
+/// it is not lost. Use `try_to_katakana` to find the romaji that could
+/// not be converted (e.g. the `q` in `qa`).
+pub fn to_katakana(input: &str) -> String {
+    to_kana(input, true).0
+}
+
+/// Same as `to_katakana`, but fails on the first romaji that cannot
+/// be converted, with its range of characters in `input`.
+///
+/// Text other than romaji, such as digits and punctuation, is kept as
+/// is and is not an error.
+///
+/// ```
+/// use kana::{try_to_katakana, SyllableError};
+///
+/// assert_eq!(try_to_katakana("kana!").unwrap(), "カナ!");
+///
+/// let err = try_to_katakana("kaqqa").unwrap_err();
+/// assert_eq!(err.position, 2..4);
+/// assert_eq!(err.reason, SyllableError::Romaji);
+/// ```
+pub fn try_to_katakana(input: &str) -> Result<String, ConversionError> {
+    try_to_kana(input, true)
+}
+
+/// Converts any hiragana in the text to katakana (e.g. `ひらがな` is
+/// `ヒラガナ`), keeping other characters as is.
+///
+/// The input is normalized with [`normalize`](fn.normalize.html).
+pub fn to_katakana_script(input: &str) -> String {
+    to_script(input, Script::Katakana)
+}
+
+/// Converts any katakana in the text to hiragana (e.g. `カタカナ` is
+/// `かたかな`), keeping other characters as is.
+///
+/// Katakana without an hiragana equivalent (e.g. `ヷ` and `ー`) is
+/// kept. The input is normalized with [`normalize`](fn.normalize.html).
+pub fn to_hiragana_script(input: &str) -> String {
+    to_script(input, Script::Hiragana)
+}
+
+fn to_script(input: &str, script: Script) -> String {
+    normalize(input)
+        .text
+        .chars()
+        .map(|chr| {
+            Kana::get(chr)
+                .and_then(|x| x.to_script(script))
+                .map_or(chr, |x| x.get_char())
+        })
+        .collect()
+}
+
+fn try_to_kana(input: &str, katakana: bool) -> Result<String, ConversionError> {
+    let (out, invalid) = to_kana(input, katakana);
+    match invalid.into_iter().next() {
+        Some(position) => Err(ConversionError {
+            position,
+            reason: SyllableError::Romaji,
+        }),
+        None => Ok(out),
+    }
+}
+
+/// Converts the romaji to kana. Also returns the range of characters
+/// in `input` for each run of romaji that could not be converted.
+fn to_kana(input: &str, katakana: bool) -> (String, Vec<Range<usize>>) {
+    let table = if katakana {
+        &ROMAJI_TABLE.katakana
+    } else {
+        &ROMAJI_TABLE.hiragana
+    };
+    let small_tsu = if katakana { 'ッ' } else { 'っ' };
+    let syllabic_n = if katakana { 'ン' } else { 'ん' };
+
+    // Vowels with macron or circumflex are split into the vowel and
+    // the combining mark. Each character keeps its source range in
+    // the input.
+    let normalized = normalize(input);
+    let (input, source): (Vec<char>, Vec<Range<usize>>) = normalized
+        .text
+        .chars()
+        .zip(normalized.source)
+        .flat_map(|(chr, range)| chr.to_lowercase().map(move |chr| (chr, range.clone())))
+        .flat_map(|(chr, range)| match ACCENTS.iter().find(|x| x.0 == chr) {
+            Some(&(_, vowel, mark)) => vec![(vowel, range.clone()), (mark, range)],
+            None => vec![(chr, range)],
+        })
+        .unzip();
+
+    // Lookup the longest romaji syllable at the given position,
+    // returning its kana and length.
+    let lookup = |pos: usize| -> Option<(&String, usize)> {
+        let max_len = ROMAJI_TABLE.max_len.min(input.len() - pos);
+        for len in (1..max_len + 1).rev() {
+            let key: String = input[pos..pos + len].iter().collect();
+            if let Some(kana) = table.get(&key) {
+                return Some((kana, len));
+            }
+        }
+        None
+    };
+
+    let mut out = String::new();
+    let mut invalid: Vec<Range<usize>> = Vec::new();
+
+    // Vowel of the last syllable, used for the katakana long vowels.
+    let mut last_vowel = None;
+
+    let mut pos = 0;
+    while pos < input.len() {
+        let chr = input[pos];
+        let next = input.get(pos + 1).cloned();
+
+        if chr == '-' {
+            out.push('ー');
+            pos += 1;
+            continue;
+        }
+
+        // Syllabic `n`. A double `nn` before a vowel is read as `n`
+        // followed by a syllable starting with `n` (e.g. `konnichiwa`).
+        // Traditional Hepburn marks it with `n-` before a vowel or `y`.
+        if chr == 'n' {
+            let after = input.get(pos + 2).cloned();
+            let len = match next {
+                Some('\'') => 2,
+                Some('-') if after.is_some_and(|c| is_vowel(c) || c == 'y') => 2,
+                Some('n') => {
+                    if after.is_some_and(|c| is_vowel(c) || c == 'y') {
+                        1
+                    } else {
+                        2
+                    }
+                }
+                Some(c) if is_vowel(c) || c == 'y' => 0,
+                _ => 1,
+            };
+            if len > 0 {
+                out.push(syllabic_n);
+                last_vowel = None;
+                pos += len;
+                continue;
+            }
+        }
+
+        // Traditional Hepburn `m` before a labial consonant.
+        if chr == 'm' && (next == Some('b') || next == Some('p') || next == Some('m')) {
+            out.push(syllabic_n);
+            last_vowel = None;
+            pos += 1;
+            continue;
+        }
+
+        // Doubled consonants, including the exceptional `tch`.
+        if chr.is_ascii_alphabetic()
+            && !is_vowel(chr)
+            && (next == Some(chr) || (chr == 't' && next == Some('c')))
+            && lookup(pos + 1).is_some()
+        {
+            out.push(small_tsu);
+            last_vowel = None;
+            pos += 1;
+            continue;
+        }
+
+        // Long vowel from a macron or circumflex. For hiragana, this is
+        // spelled with the vowel kana, using `う` for a long `o`.
+        if let (MACRON, Some(vowel)) | (CIRCUMFLEX, Some(vowel)) = (chr, last_vowel) {
+            if katakana {
+                out.push('ー');
+            } else {
+                let vowel = if vowel == 'o' { 'u' } else { vowel };
+                out.push_str(&table[&vowel.to_string()]);
+            }
+            pos += 1;
+            continue;
+        }
+
+        if katakana && is_vowel(chr) && last_vowel == Some(chr) {
+            out.push('ー');
+            pos += 1;
+            continue;
+        }
+
+        if let Some((kana, len)) = lookup(pos) {
+            out.push_str(kana);
+            last_vowel = Some(input[pos + len - 1]).filter(|&c| is_vowel(c));
+            pos += len;
+        } else {
+            if chr.is_ascii_alphabetic() || chr == MACRON || chr == CIRCUMFLEX {
+                let range = source[pos].clone();
+                match invalid.last_mut() {
+                    Some(last) if last.end >= range.start => last.end = range.end,
+                    _ => invalid.push(range),
+                }
+            }
+            out.push(chr);
+            last_vowel = None;
+            pos += 1;
+        }
+    }
+
+    (out, invalid)
+}
+
+fn is_vowel(chr: char) -> bool {
+    matches!(chr, 'a' | 'i' | 'u' | 'e' | 'o')
+}
+
+// Kana that are read by `to_romaji` but never generated by the
+// conversion, since they have a more common spelling.
+fn is_rare(chr: char) -> bool {
+    "ゐゑゕゖゎヰヱヷヸヹヺヵヶヮ".contains(chr)
+}
+
+fn is_katakana(chr: char) -> bool {
+    ('\u{30A0}'..='\u{30FF}').contains(&chr)
+}
+
+/// Reverse lookup tables from romaji syllables to kana.
+struct RomajiTable {
+    hiragana: HashMap<String, String>,
+    katakana: HashMap<String, String>,
+    max_len: usize,
+}
+
+lazy_static! {
+    static ref ROMAJI_TABLE: RomajiTable = {
+        // The same romaji can be generated by different kana (e.g.
+        // `ティ` and `トィ` are both `ti`), so we keep a rank for
+        // each entry and choose the one with the lowest rank.
+        type Rank = (u8, u8, u8, u8, u8);
+        type Map = HashMap<String, (Rank, String)>;
+
+        fn insert(map: &mut Map, romaji: String, rank: Rank, kana: String) {
+            if romaji.contains('~') {
+                return;
+            }
+            let entry = map.entry(romaji).or_insert_with(|| (rank, kana.clone()));
+            if rank < entry.0 {
+                *entry = (rank, kana);
+            }
+        }
+
+        let mut hiragana_map = Map::new();
+        let mut katakana_map = Map::new();
+
+        // Spellings from the default system have precedence over the
+        // others. Other than that, single characters have precedence
+        // over digraphs, except for katakana where the digraphs are
+        // common in loanwords (e.g. `ti` is `ち` and `ティ`).
+        fn tier(katakana: bool, default: bool, digraph: bool) -> u8 {
+            match (default, digraph) {
+                (true, false) => 0,
+                (true, true) => if katakana { 1 } else { 2 },
+                (false, false) => if katakana { 2 } else { 1 },
+                (false, true) => if katakana { 3 } else { 2 },
+            }
+        }
+
+        for (index, &system) in RomanizationSystem::ALL.iter().enumerate() {
+            let default = system == RomanizationSystem::default();
+            let index = index as u8;
+
+            for kana in TABLE {
+                let (chr, romaji) = match kana {
+                    Kana::Chr(chr, romaji) | Kana::Dig(chr, romaji, _) => (*chr, *romaji),
+                    _ => continue,
+                };
+                if is_rare(chr) {
+                    continue;
+                }
+                let katakana = is_katakana(chr);
+                let rank = (tier(katakana, default, false), 0, 0, 0, index);
+                let map = if katakana { &mut katakana_map } else { &mut hiragana_map };
+                insert(map, system.syllable(romaji).to_string(), rank, chr.to_string());
+            }
+
+            // Generate the digraphs by combining each character with
+            // the small kana of the same script.
+            for base in TABLE {
+                let (base, romaji, is_dig) = match base {
+                    Kana::Chr(chr, romaji) => (*chr, *romaji, false),
+                    Kana::Dig(chr, romaji, _) => (*chr, *romaji, true),
+                    _ => continue,
+                };
+                if is_rare(base) {
+                    continue;
+                }
+                for small in TABLE {
+                    let (small, suffix) = match small {
+                        Kana::Small(chr, suffix) => (*chr, *suffix),
+                        _ => continue,
+                    };
+                    let katakana = is_katakana(base);
+                    if katakana != is_katakana(small) || is_rare(small) {
+                        continue;
+                    }
+
+                    // Rank the digraph by the most common forms: small
+                    // `ya`, `yu`, `yo` before the small vowels, explicit
+                    // digraph prefixes and then by the base vowel (e.g.
+                    // `キャ`, `テュ`, `フュ`, `ファ`, `シェ`, `ティ` and `トゥ`).
+                    //
+                    // Other combinations are not generated.
+                    let suffix = suffix.as_str();
+                    let is_y = suffix.starts_with('y');
+                    let vowel = suffix.chars().last().unwrap();
+                    let base_vowel = romaji.chars().last().unwrap();
+                    let vowel_rank = match (base_vowel, vowel) {
+                        ('i', _) if is_y => 0,
+                        ('e', _) if is_y && (romaji == "te" || romaji == "de") => 1,
+                        ('u', _) if is_y && (romaji == "fu" || romaji == "vu") => 2,
+                        (_, _) if is_y => continue,
+                        ('u', _) => 0,
+                        ('i', _) => 1,
+                        ('e', 'i') | ('e', 'e') | ('o', 'u') | ('o', 'o') => 2,
+                        _ => continue,
+                    };
+                    let rank = (
+                        tier(katakana, default, true),
+                        !is_y as u8,
+                        !is_dig as u8,
+                        vowel_rank,
+                        index,
+                    );
+
+                    let mut kana = String::new();
+                    kana.push(base);
+                    kana.push(small);
+                    let map = if katakana { &mut katakana_map } else { &mut hiragana_map };
+                    insert(map, to_romaji(&kana, system, LongVowels::Doubled), rank, kana);
+                }
+            }
+        }
+
+        fn finish(map: Map) -> HashMap<String, String> {
+            map.into_iter().map(|(k, (_, v))| (k, v)).collect()
+        }
+
+        let max_len = hiragana_map
+            .keys()
+            .chain(katakana_map.keys())
+            .map(|x| x.len())
+            .max()
+            .unwrap_or(0);
+        RomajiTable {
+            hiragana: finish(hiragana_map),
+            katakana: finish(katakana_map),
+            max_len,
+        }
+    };
+}
+
+// spell-checker: disable
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_to_kana_basic() {
+        assert_eq!(to_hiragana(""), "");
+        assert_eq!(to_hiragana("aiueo"), "あいうえお");
+        assert_eq!(to_hiragana("ｋａｎａ"), "かな");
+        assert_eq!(to_katakana("aiueo"), "アイウエオ");
+        assert_eq!(to_hiragana("kakikukeko"), "かきくけこ");
+        assert_eq!(to_hiragana("sashisuseso"), "さしすせそ");
+        assert_eq!(to_hiragana("tachitsuteto"), "たちつてと");
+        assert_eq!(to_hiragana("dajizudedo"), "だじずでど");
+        assert_eq!(to_hiragana("hahifuheho"), "はひふへほ");
+        assert_eq!(to_hiragana("wawo"), "わを");
+        assert_eq!(to_hiragana("ASOBI"), "あそび");
+        assert_eq!(to_hiragana("[a] b"), "[あ] b");
+    }
+
+    #[test]
+    fn test_to_script() {
+        assert_eq!(to_katakana_script("ひらがな"), "ヒラガナ");
+        assert_eq!(to_katakana_script("きゃっ、ゝゞ abc"), "キャッ、ヽヾ abc");
+        assert_eq!(to_hiragana_script("カタカナ"), "かたかな");
+        assert_eq!(to_hiragana_script("コーヒー ヷ"), "こーひー ヷ");
+        assert_eq!(to_hiragana_script("ｶﾞｯｺｳ"), "がっこう");
+        assert_eq!(to_hiragana_script("ヴヵヶ"), "ゔゕゖ");
+    }
+
+    #[test]
+    fn test_to_kana_round_trip() {
+        use tables::RomanizationSystem::*;
+        for kana in TABLE {
+            if let Kana::Chr(chr, _) | Kana::Dig(chr, _, _) = kana {
+                if is_rare(*chr) {
+                    continue;
+                }
+                let expected = chr.to_string();
+                if is_katakana(*chr) {
+                    // These are ambiguous in Hepburn
+                    if "ヂヅヲ".contains(*chr) {
+                        continue;
+                    }
+                    let romaji = to_romaji(&expected, ModifiedHepburn, LongVowels::Doubled);
+                    assert_eq!(to_katakana(&romaji), expected);
+                } else {
+                    for &system in &[NihonShiki, Wapuro] {
+                        let romaji = to_romaji(&expected, system, LongVowels::Doubled);
+                        assert_eq!(to_hiragana(&romaji), expected);
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_to_kana_round_trip_systems() {
+        use tables::RomanizationSystem::*;
+
+        // The words avoid the kana that have the same spelling in some
+        // systems (e.g. `ぢ` and `じ` are both `ji` in Hepburn).
+        let hiragana = "きんえん こんや しんぶん さんま がっこう しゃしん おかあさん ちゃわん";
+        let katakana = "コーヒー ケーキ パン カメラ";
+        for &system in RomanizationSystem::ALL {
+            // Wāpuro spells `ん` as `nn` before a vowel, which is read
+            // as `n` and a syllable starting with `n` (e.g. `konnichiwa`).
+            let hiragana = if system == Wapuro {
+                "しんぶん さんま がっこう しゃしん おかあさん ちゃわん"
+            } else {
+                hiragana
+            };
+            let romaji = to_romaji(hiragana, system, LongVowels::Doubled);
+            assert_eq!(to_hiragana(&romaji), hiragana, "{:?}: {}", system, romaji);
+            let romaji = to_romaji(katakana, system, LongVowels::Doubled);
+            assert_eq!(to_katakana(&romaji), katakana, "{:?}: {}", system, romaji);
+        }
+
+        assert_eq!(to_hiragana("kin-en kon-ya"), "きんえん こんや");
+        assert_eq!(to_hiragana("n-"), "んー");
+    }
+
+    #[test]
+    fn test_to_kana_systems() {
+        assert_eq!(to_hiragana("sashisuseso"), "さしすせそ");
+        assert_eq!(to_hiragana("sasisuseso"), "さしすせそ");
+        assert_eq!(to_hiragana("tatituteto"), "たちつてと");
+        assert_eq!(to_hiragana("hahihuheho"), "はひふへほ");
+        assert_eq!(to_hiragana("zazizuzezo"), "ざじずぜぞ");
+        assert_eq!(to_hiragana("dadidudedo"), "だぢづでど");
+        assert_eq!(to_hiragana("syasyusyo"), "しゃしゅしょ");
+        assert_eq!(to_hiragana("tyatyutyo"), "ちゃちゅちょ");
+        assert_eq!(to_hiragana("zyazyuzyo"), "じゃじゅじょ");
+        assert_eq!(to_hiragana("dyadyudyo"), "ぢゃぢゅぢょ");
+        assert_eq!(to_hiragana("wo"), "を");
+        assert_eq!(to_katakana("sye"), "シェ");
+    }
+
+    #[test]
+    fn test_to_kana_small_tsu() {
+        assert_eq!(to_hiragana("matte"), "まって");
+        assert_eq!(to_katakana("matte"), "マッテ");
+        assert_eq!(to_hiragana("kotchi"), "こっち");
+        assert_eq!(to_hiragana("assari"), "あっさり");
+        assert_eq!(to_katakana("fasshon"), "ファッション");
+        assert_eq!(to_katakana("kotchi"), "コッチ");
+        assert_eq!(to_katakana("purettseru"), "プレッツェル");
+    }
+
+    #[test]
+    fn test_to_kana_n() {
+        assert_eq!(to_hiragana("n"), "ん");
+        assert_eq!(to_hiragana("nn"), "ん");
+        assert_eq!(to_hiragana("hon"), "ほん");
+        assert_eq!(to_hiragana("kinen"), "きねん");
+        assert_eq!(to_hiragana("kin'en"), "きんえん");
+        assert_eq!(to_hiragana("kinnen"), "きんねん");
+        assert_eq!(to_hiragana("konnichiwa"), "こんにちわ");
+        assert_eq!(to_hiragana("kon'ya"), "こんや");
+        assert_eq!(to_hiragana("konya"), "こにゃ");
+        assert_eq!(to_hiragana("shinbun"), "しんぶん");
+        assert_eq!(to_hiragana("shimbun"), "しんぶん");
+        assert_eq!(to_hiragana("semmon"), "せんもん");
+    }
+
+    #[test]
+    fn test_to_kana_digraph() {
+        assert_eq!(to_hiragana("kyagyushojachanyo"), "きゃぎゅしょじゃちゃにょ");
+        assert_eq!(to_katakana("kyagyushojachanyo"), "キャギュショジャチャニョ");
+        assert_eq!(to_katakana("fafifufefo"), "ファフィフフェフォ");
+        assert_eq!(to_katakana("vavivuvevo"), "ヴァヴィヴヴェヴォ");
+        assert_eq!(to_katakana("wawiuwewo"), "ワウィウウェウォ");
+        assert_eq!(to_katakana("kwa gwa"), "クァ グァ");
+        assert_eq!(to_katakana("shejeche"), "シェジェチェ");
+        assert_eq!(to_katakana("tatituteto"), "タティトゥテト");
+        assert_eq!(to_katakana("dadidudedo"), "ダディドゥデド");
+        assert_eq!(to_katakana("tyu dyu fyu"), "テュ デュ フュ");
+        assert_eq!(to_katakana("yei"), "イェイ");
+        assert_eq!(to_katakana("tsa"), "ツァ");
+        assert_eq!(to_hiragana("vavu wiwe kwa"), "ゔぁゔ うぃうぇ くぁ");
+    }
+
+    #[test]
+    fn test_to_kana_long() {
+        assert_eq!(to_katakana("paatii"), "パーティー");
+        assert_eq!(to_katakana("pa-ti-"), "パーティー");
+        assert_eq!(to_katakana("fooku"), "フォーク");
+        assert_eq!(to_katakana("keeki"), "ケーキ");
+        assert_eq!(to_katakana("kei"), "ケイ");
+        assert_eq!(to_hiragana("okaasan"), "おかあさん");
+        assert_eq!(to_hiragana("ra-men"), "らーめん");
+        assert_eq!(to_katakana("kōhī"), "コーヒー");
+        assert_eq!(to_katakana("pâtî"), "パーティー");
+        assert_eq!(to_hiragana("tōkyō"), "とうきょう");
+        assert_eq!(to_hiragana("onēsan"), "おねえさん");
+        assert_eq!(to_hiragana("Ōsaka"), "おうさか");
+    }
+
+    #[test]
+    fn test_to_kana_invalid() {
+        assert_eq!(to_hiragana("qa"), "qあ");
+        assert_eq!(to_hiragana("kx"), "kx");
+        assert_eq!(to_hiragana("xka"), "xか");
+        assert_eq!(to_katakana("kka"), "ッカ");
+        assert_eq!(to_katakana("1ka!"), "1カ!");
+    }
+
+    #[test]
+    fn test_try_to_kana() {
+        let position = |input: &str| try_to_hiragana(input).unwrap_err().position;
+
+        assert_eq!(try_to_hiragana("kana").unwrap(), "かな");
+        assert_eq!(try_to_hiragana("1ka!").unwrap(), "1か!");
+        assert_eq!(try_to_katakana("kōhī").unwrap(), "コーヒー");
+        assert_eq!(position("qa"), 0..1);
+        assert_eq!(position("kx"), 0..2);
+        assert_eq!(position("ka x ka"), 3..4);
+        assert_eq!(position("ｋａｑ"), 2..3);
+        assert_eq!(
+            try_to_katakana("kaq").unwrap_err().reason,
+            SyllableError::Romaji
+        );
+    }
+}
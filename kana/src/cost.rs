@@ -0,0 +1,160 @@
+use diff::CostModel;
+
+/// Pairs of consonants that are easily confused, either because they
+/// only differ in voicing (e.g. `k` and `g`) or because they sound
+/// alike (e.g. `sh` and `ch`).
+const SIMILAR_CONSONANTS: &[(&str, &str)] = &[
+    // Voicing (dakuten and handakuten)
+    ("k", "g"),
+    ("s", "z"),
+    ("sh", "j"),
+    ("t", "d"),
+    ("ch", "j"),
+    ("ts", "z"),
+    ("h", "b"),
+    ("h", "p"),
+    ("f", "b"),
+    ("f", "p"),
+    ("b", "p"),
+    // Similar sounds
+    ("s", "sh"),
+    ("sh", "ch"),
+    ("s", "ts"),
+    ("t", "ts"),
+    ("t", "ch"),
+    ("ts", "ch"),
+    ("z", "j"),
+    ("d", "j"),
+    ("d", "z"),
+    ("h", "f"),
+    ("r", "l"),
+    ("m", "n"),
+    ("w", ""),
+    ("y", ""),
+];
+
+/// `CostModel` for romaji that makes the mistakes a learner usually
+/// does cheaper, so they are aligned to the syllable they belong to:
+///
+/// - Changing a syllable to one with the same vowel and a similar
+///   consonant (e.g. `chi` for `shi`, `su` for `tsu` or `ga` for
+///   `ka`) costs 1.
+/// - Changing a syllable to one with the same consonant or the same
+///   vowel costs 2.
+/// - Missing a sokuon (e.g. `gakou` for `gakkou`) or doubling a
+///   consonant (e.g. `kitte` for `kite`) costs 1.
+///
+/// Everything else has the cost by length, as in `LengthCost`.
+///
+/// ```
+/// use kana::{CostModel, PhoneticCost};
+///
+/// assert_eq!(PhoneticCost.change("chi", "shi"), Some(1));
+/// assert_eq!(PhoneticCost.change("ki", "shi"), Some(2));
+/// assert_eq!(PhoneticCost.change("ka", "shi"), None);
+/// ```
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PhoneticCost;
+
+impl CostModel for PhoneticCost {
+    fn insert(&self, syllable: &str) -> usize {
+        if is_sokuon(syllable) {
+            1
+        } else {
+            syllable.len() + 1
+        }
+    }
+
+    fn delete(&self, text: &str, next: &str) -> Option<usize> {
+        if is_sokuon(text) && next.starts_with(text) {
+            Some(1)
+        } else {
+            None
+        }
+    }
+
+    fn change(&self, text: &str, syllable: &str) -> Option<usize> {
+        let (c1, v1) = split_syllable(text)?;
+        let (c2, v2) = split_syllable(syllable)?;
+        if v1.is_empty() || v2.is_empty() {
+            None
+        } else if v1 == v2 && is_similar(c1, c2) {
+            Some(1)
+        } else if v1 == v2 || (c1 == c2 && !c1.is_empty()) {
+            Some(2)
+        } else {
+            None
+        }
+    }
+}
+
+/// True for the romaji of a sokuon, which is the consonant that is
+/// doubled (e.g. `k` in `kko`).
+fn is_sokuon(text: &str) -> bool {
+    let mut chars = text.chars();
+    match (chars.next(), chars.next()) {
+        (Some(chr), None) => chr.is_ascii_lowercase() && !"aeioun".contains(chr),
+        _ => false,
+    }
+}
+
+/// Splits a romaji syllable in the consonant and vowel. Returns `None`
+/// if the text is not a romaji syllable.
+fn split_syllable(text: &str) -> Option<(&str, &str)> {
+    let is_vowel = |chr: char| "aeiou\u{304}\u{302}".contains(chr);
+    let bytes = text.as_bytes();
+    let index = bytes
+        .iter()
+        .position(|&x| !x.is_ascii_lowercase() || is_vowel(x as char))
+        .unwrap_or(bytes.len());
+    let (consonant, vowel) = text.split_at(index);
+
+    // Consonants have at most two letters (e.g. `sh`, `ts` or `ky`).
+    let is_consonant = match consonant.as_bytes() {
+        [] | [_] => true,
+        [_, second] => b"hswy".contains(second),
+        _ => false,
+    };
+    if text.is_empty() || !is_consonant || !vowel.chars().all(is_vowel) {
+        None
+    } else {
+        Some((consonant, vowel))
+    }
+}
+
+fn is_similar(a: &str, b: &str) -> bool {
+    SIMILAR_CONSONANTS
+        .iter()
+        .any(|&(x, y)| (x == a && y == b) || (x == b && y == a))
+}
+
+// spell-checker: disable
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_phonetic_cost() {
+        let cost = PhoneticCost;
+        assert_eq!(cost.change("su", "tsu"), Some(1));
+        assert_eq!(cost.change("ba", "pa"), Some(1));
+        assert_eq!(cost.change("o", "wo"), Some(1));
+        assert_eq!(cost.change("ke", "ki"), Some(2));
+        assert_eq!(cost.change("o", "ko"), Some(2));
+        assert_eq!(cost.change("k", "ka"), None);
+        assert_eq!(cost.change("ka", "n"), None);
+        assert_eq!(cost.change("xyz", "ka"), None);
+        assert_eq!(cost.change("kqta", "ka"), None);
+        assert_eq!(cost.change("kya", "ka"), Some(2));
+        assert_eq!(cost.change("か", "ka"), None);
+
+        assert_eq!(cost.insert("k"), 1);
+        assert_eq!(cost.insert("n"), 2);
+        assert_eq!(cost.insert("ka"), 3);
+
+        assert_eq!(cost.delete("t", "te"), Some(1));
+        assert_eq!(cost.delete("t", "ka"), None);
+        assert_eq!(cost.delete("a", "a"), None);
+    }
+}
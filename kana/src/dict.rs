@@ -0,0 +1,710 @@
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use tables::Kana;
+use words::Word;
+
+/// Dictionary entry for a word.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct DictEntry {
+    /// The word, as written in the dictionary.
+    pub word: String,
+
+    /// Kana readings for the word, with the most common first.
+    pub readings: Vec<String>,
+
+    /// English meanings for the word.
+    pub glosses: Vec<String>,
+
+    /// Part of speech tags for the word (e.g. `n` or `v5r`).
+    pub pos: Vec<String>,
+
+    /// True if the word is usually written in kana (the `uk` tag), in
+    /// which case `Dictionary::get` also finds it by its readings.
+    pub usually_kana: bool,
+}
+
+/// Dictionary of kana readings and meanings, used to train with words
+/// that are written with kanji and to show the meaning of words.
+///
+/// Words are indexed both by how they are written and by their kana
+/// readings. Words usually written in kana are also indexed by their
+/// readings as if they were written that way (e.g. `為る` as `する`).
+#[derive(Debug, Clone, Default)]
+pub struct Dictionary {
+    entries: Vec<DictEntry>,
+    by_word: HashMap<String, usize>,
+    by_kana: HashMap<String, Vec<usize>>,
+}
+
+impl Dictionary {
+    /// Loads a dictionary file in either the JMdict XML or the EDICT
+    /// format, depending on its contents.
+    pub fn open<P: AsRef<Path>>(path: P) -> io::Result<Dictionary> {
+        let text = fs::read_to_string(path)?;
+        if text.trim_start().starts_with('<') {
+            Ok(Dictionary::parse_jmdict(&text))
+        } else {
+            Ok(Dictionary::parse_edict(&text))
+        }
+    }
+
+    /// Loads a dictionary file in the EDICT or EDICT2 format. The file
+    /// must be in UTF-8 (e.g. `edict2u`).
+    pub fn open_edict<P: AsRef<Path>>(path: P) -> io::Result<Dictionary> {
+        let text = fs::read_to_string(path)?;
+        Ok(Dictionary::parse_edict(&text))
+    }
+
+    /// Parses a dictionary in the EDICT or EDICT2 format:
+    ///
+    /// ```text
+    /// 年;歳 [とし(年,歳)(P);とせ(歳)] /(n) (1) year/(2) age/EntL1457800X/
+    /// ```
+    ///
+    /// Lines that are not in the format are ignored. If a word has
+    /// more than one entry, `get` returns the first.
+    ///
+    /// ```
+    /// use kana::Dictionary;
+    ///
+    /// let dict = Dictionary::parse_edict("年;歳 [とし(P);ねん] /(n) year/age/EntL1/\n");
+    /// assert_eq!(dict.reading("歳"), Some("とし"));
+    /// assert_eq!(dict.get("年").unwrap().glosses, vec!["year", "age"]);
+    /// ```
+    pub fn parse_edict(text: &str) -> Dictionary {
+        let mut dict = Dictionary::default();
+        for line in text.lines() {
+            let (head, rest) = match line.find(" /") {
+                Some(pos) => (line[..pos].trim(), &line[pos + 2..]),
+                None => continue,
+            };
+
+            // Headwords and readings (e.g. `年;歳 [とし;とせ]`).
+            let (words, readings) = match head.find(" [") {
+                Some(pos) if head.ends_with(']') => {
+                    (&head[..pos], Some(&head[pos + 2..head.len() - 1]))
+                }
+                _ => (head, None),
+            };
+
+            let mut glosses = Vec::new();
+            let mut pos = Vec::new();
+            let mut usually_kana = false;
+            for (index, field) in rest.split('/').enumerate() {
+                let (tags, gloss) = split_tags(field);
+                if index == 0 {
+                    if let Some(tags) = tags.first().filter(|&&x| x != "uk") {
+                        pos = tags.split(',').map(String::from).collect();
+                    }
+                }
+                if tags.iter().any(|x| x.split(',').any(|x| x == "uk")) {
+                    usually_kana = true;
+                }
+                if !gloss.is_empty() && !gloss.starts_with("EntL") {
+                    glosses.push(gloss.to_string());
+                }
+            }
+
+            for word in words.split(';') {
+                let (word, _) = split_marks(word);
+                if word.is_empty() {
+                    continue;
+                }
+
+                // Entries without a reading must be in kana.
+                if readings.is_none() && !word.chars().all(|x| Kana::get(x).is_some()) {
+                    continue;
+                }
+
+                // Readings can be restricted to some of the words, and
+                // the common ones are marked with `(P)`.
+                let mut common = Vec::new();
+                let mut others = Vec::new();
+                for reading in readings.unwrap_or(word).split(';') {
+                    let (reading, marks) = split_marks(reading);
+                    let restricted = marks.iter().any(|x| x != &"P");
+                    if reading.is_empty()
+                        || restricted && !marks.iter().any(|x| x.split(',').any(|x| x == word))
+                    {
+                        continue;
+                    }
+                    if marks.contains(&"P") {
+                        common.push(reading.to_string());
+                    } else {
+                        others.push(reading.to_string());
+                    }
+                }
+                common.extend(others);
+
+                dict.insert(DictEntry {
+                    word: word.to_string(),
+                    readings: common,
+                    glosses: glosses.clone(),
+                    pos: pos.clone(),
+                    usually_kana,
+                });
+            }
+        }
+        dict
+    }
+
+    /// Parses a dictionary in the JMdict XML format. Only the English
+    /// glosses are used, and the parts of speech are the entity names
+    /// (e.g. `n` for `&n;`).
+    ///
+    /// ```
+    /// use kana::Dictionary;
+    ///
+    /// let dict = Dictionary::parse_jmdict(
+    ///     "<JMdict><entry><ent_seq>1</ent_seq>\
+    ///      <k_ele><keb>猫</keb></k_ele><r_ele><reb>ねこ</reb></r_ele>\
+    ///      <sense><pos>&n;</pos><gloss>cat</gloss></sense>\
+    ///      </entry></JMdict>",
+    /// );
+    /// assert_eq!(dict.reading("猫"), Some("ねこ"));
+    /// assert_eq!(dict.lookup_kana("ねこ")[0].glosses, vec!["cat"]);
+    /// ```
+    pub fn parse_jmdict(text: &str) -> Dictionary {
+        let mut dict = Dictionary::default();
+        let mut rest = text;
+        while let Some(entry) = next_element(&mut rest, "entry") {
+            let mut kanji = Vec::new();
+            let mut readings: Vec<Reading> = Vec::new();
+            let mut glosses = Vec::new();
+            let mut pos = Vec::new();
+            let mut usually_kana = false;
+
+            let mut elements = entry;
+            while let Some((name, _, element)) = next_child(&mut elements) {
+                match name {
+                    "k_ele" => {
+                        if let Some(keb) = element_text(element, "keb") {
+                            kanji.push((keb, element.contains("<ke_pri>")));
+                        }
+                    }
+                    "r_ele" => {
+                        if let Some(reb) = element_text(element, "reb") {
+                            let mut restrict = Vec::new();
+                            let mut inner = element;
+                            while let Some(text) = next_element(&mut inner, "re_restr") {
+                                restrict.push(unescape(text));
+                            }
+                            readings.push(Reading {
+                                text: reb,
+                                common: element.contains("<re_pri>"),
+                                no_kanji: element.contains("<re_nokanji"),
+                                restrict,
+                            });
+                        }
+                    }
+                    "sense" => {
+                        let mut inner = element;
+                        while let Some((name, attributes, text)) = next_child(&mut inner) {
+                            match name {
+                                "pos" if glosses.is_empty() => {
+                                    let text = text.trim().trim_start_matches('&');
+                                    pos.push(text.trim_end_matches(';').to_string());
+                                }
+                                "gloss" if is_english(attributes) => glosses.push(unescape(text)),
+                                "misc" if text.trim() == "&uk;" => usually_kana = true,
+                                _ => {}
+                            }
+                        }
+                    }
+                    _ => {}
+                }
+            }
+
+            // Common readings are listed first.
+            readings.sort_by_key(|x| !x.common);
+            kanji.sort_by_key(|x| !x.1);
+
+            let mut words: Vec<(String, Vec<String>)> = Vec::new();
+            for (keb, _) in kanji {
+                let list = readings
+                    .iter()
+                    .filter(|x| !x.no_kanji && (x.restrict.is_empty() || x.restrict.contains(&keb)))
+                    .map(|x| x.text.clone())
+                    .collect();
+                words.push((keb, list));
+            }
+            // Words that are only written in kana.
+            let no_kanji = words.is_empty();
+            for it in readings.iter().filter(|x| no_kanji || x.no_kanji) {
+                words.push((it.text.clone(), vec![it.text.clone()]));
+            }
+
+            for (word, readings) in words {
+                dict.insert(DictEntry {
+                    word,
+                    readings,
+                    glosses: glosses.clone(),
+                    pos: pos.clone(),
+                    usually_kana,
+                });
+            }
+        }
+        dict
+    }
+
+    /// Writes the dictionary in the EDICT format, which can be loaded
+    /// much faster than the JMdict XML.
+    pub fn write_edict<W: io::Write>(&self, mut out: W) -> io::Result<()> {
+        for it in &self.entries {
+            let is_kana = it.readings.len() == 1 && it.readings[0] == it.word;
+            let mut line = it.word.clone();
+            if !is_kana {
+                line.push_str(&format!(" [{}]", it.readings.join(";")));
+            }
+            line.push_str(" /");
+            if !it.pos.is_empty() {
+                line.push_str(&format!("({}) ", it.pos.join(",")));
+            }
+            if it.usually_kana {
+                line.push_str("(uk) ");
+            }
+            for gloss in &it.glosses {
+                line.push_str(&gloss.replace('/', ", "));
+                line.push('/');
+            }
+            writeln!(out, "{}", line)?;
+        }
+        Ok(())
+    }
+
+    fn insert(&mut self, entry: DictEntry) {
+        let index = self.entries.len();
+        self.by_word.entry(entry.word.clone()).or_insert(index);
+        for reading in &entry.readings {
+            let list = self.by_kana.entry(reading.clone()).or_default();
+            list.push(index);
+            if entry.usually_kana {
+                self.by_word.entry(reading.clone()).or_insert(index);
+            }
+        }
+        self.entries.push(entry);
+    }
+
+    /// Returns the entry for a word, as it is written. If the word has
+    /// more than one entry, this is the first one.
+    pub fn get(&self, word: &str) -> Option<&DictEntry> {
+        self.by_word.get(word).map(|&x| &self.entries[x])
+    }
+
+    /// Returns all the entries for words with the given kana reading,
+    /// in the dictionary order.
+    pub fn lookup_kana(&self, kana: &str) -> Vec<&DictEntry> {
+        match self.by_kana.get(kana) {
+            Some(list) => list.iter().map(|&x| &self.entries[x]).collect(),
+            None => Vec::new(),
+        }
+    }
+
+    /// Returns the English meanings for a word, looking it up first
+    /// as it is written and then by its kana reading.
+    ///
+    /// Words with the same reading are not merged. If the word is not
+    /// found, this uses the first entry where `kana` is the most common
+    /// reading, or else the first entry with that reading.
+    pub fn glosses(&self, word: &str, kana: &str) -> Vec<String> {
+        if let Some(entry) = self.get(word) {
+            return entry.glosses.clone();
+        }
+        let entries = self.lookup_kana(kana);
+        let entry = entries
+            .iter()
+            .find(|x| x.readings.first().is_some_and(|x| x == kana))
+            .or(entries.first());
+        entry.map(|x| x.glosses.clone()).unwrap_or_default()
+    }
+
+    /// Returns the most common reading for a word.
+    pub fn reading(&self, word: &str) -> Option<&str> {
+        self.get(word)
+            .and_then(|x| x.readings.first())
+            .map(|x| x.as_str())
+    }
+
+    /// Returns the number of entries in the dictionary.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// True if the dictionary has no words.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Attaches the reading, meaning and part of speech from the
+    /// dictionary to the words, if they do not have them already.
+    ///
+    /// This can be used with `FileWords::from_words` to train with
+    /// the words in kanji from a list:
+    ///
+    /// ```no_run
+    /// use kana::{Dictionary, FileWords, WORDS};
+    ///
+    /// let dict = Dictionary::open_edict("edict2u").unwrap();
+    /// let words = FileWords::from_words(dict.annotate(WORDS.iter().cloned()));
+    /// ```
+    pub fn annotate<I: IntoIterator<Item = Word>>(&self, words: I) -> Vec<Word> {
+        let mut out = Vec::new();
+        for mut word in words {
+            if let Some(entry) = self.get(&word.word) {
+                let is_kana = entry.readings.iter().any(|x| x == &word.word);
+                if word.reading.is_none() && !is_kana {
+                    word.reading = entry.readings.first().cloned();
+                }
+                if word.gloss.is_none() && !entry.glosses.is_empty() {
+                    word.gloss = Some(entry.glosses.join("; "));
+                }
+                if word.pos.is_none() && !entry.pos.is_empty() {
+                    word.pos = Some(entry.pos.join(","));
+                }
+            }
+            out.push(word);
+        }
+        out
+    }
+}
+
+/// Kana reading for a JMdict entry.
+struct Reading {
+    text: String,
+    common: bool,
+    no_kanji: bool,
+    restrict: Vec<String>,
+}
+
+/// Returns the contents of the next `<name>` element in `text` and
+/// advances `text` past it.
+fn next_element<'a>(text: &mut &'a str, name: &str) -> Option<&'a str> {
+    let open = format!("<{}", name);
+    let close = format!("</{}>", name);
+    loop {
+        let start = text.find(&open)?;
+        let after = &text[start + open.len()..];
+        // Make sure this is not a longer tag name (e.g. `<entry_x>`).
+        match after.chars().next() {
+            Some('>') | Some(' ') | Some('/') => {}
+            _ => {
+                *text = after;
+                continue;
+            }
+        }
+        let tag_end = after.find('>')?;
+        if after[..tag_end].ends_with('/') {
+            *text = &after[tag_end + 1..];
+            return Some("");
+        }
+        let content = &after[tag_end + 1..];
+        let end = content.find(&close)?;
+        *text = &content[end + close.len()..];
+        return Some(&content[..end]);
+    }
+}
+
+/// Returns the name, attributes and contents of the next child element
+/// in `text` and advances `text` past it.
+fn next_child<'a>(text: &mut &'a str) -> Option<(&'a str, &'a str, &'a str)> {
+    loop {
+        let start = text.find('<')?;
+        let after = &text[start + 1..];
+        if after.starts_with('/') || after.starts_with('!') || after.starts_with('?') {
+            *text = after;
+            continue;
+        }
+        let name_end = after.find(['>', ' ', '/'])?;
+        let name = &after[..name_end];
+        let attributes = &after[name_end..after.find('>')?];
+        *text = &text[start..];
+        let content = next_element(text, name)?;
+        return Some((name, attributes.trim(), content));
+    }
+}
+
+/// True for glosses without a language or in English.
+fn is_english(attributes: &str) -> bool {
+    match attributes.find("xml:lang=") {
+        Some(pos) => attributes[pos + 9..]
+            .trim_matches(|c| c == '"' || c == '\'')
+            .starts_with("eng"),
+        None => true,
+    }
+}
+
+/// Returns the unescaped text of the first `<name>` element.
+fn element_text(text: &str, name: &str) -> Option<String> {
+    let mut text = text;
+    next_element(&mut text, name).map(unescape)
+}
+
+/// Replaces the predefined XML entities.
+fn unescape(text: &str) -> String {
+    text.trim()
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+}
+
+/// Splits the leading tags in parenthesis from an EDICT gloss (e.g.
+/// `(n,adj-no) (1) year`).
+fn split_tags(field: &str) -> (Vec<&str>, &str) {
+    let mut tags = Vec::new();
+    let mut text = field.trim();
+    while text.starts_with('(') {
+        match text.find(')') {
+            Some(end) => {
+                let tag = &text[1..end];
+                // Sense numbers are not tags.
+                if !tag.chars().all(|x| x.is_ascii_digit()) {
+                    tags.push(tag);
+                }
+                text = text[end + 1..].trim_start();
+            }
+            None => break,
+        }
+    }
+    (tags, text)
+}
+
+/// Splits the trailing marks in parenthesis from an EDICT headword or
+/// reading (e.g. `とし(年,歳)(P)`).
+fn split_marks(text: &str) -> (&str, Vec<&str>) {
+    let text = text.trim();
+    let (word, mut rest) = match text.find('(') {
+        Some(pos) => (&text[..pos], &text[pos..]),
+        None => return (text, Vec::new()),
+    };
+    let mut marks = Vec::new();
+    while let (Some(start), Some(end)) = (rest.find('('), rest.find(')')) {
+        if end < start {
+            break;
+        }
+        marks.push(&rest[start + 1..end]);
+        rest = &rest[end + 1..];
+    }
+    (word, marks)
+}
+
+// spell-checker: disable
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EDICT: &str = "\
+？？？？ /,EDICT2 Japanese-English Dictionary/EntL0000000/
+年;歳 [とし(年,歳)(P);とせ(歳);ねん(年)] /(n) (1) year/(2) age/(P)/EntL1457800X/
+機 [はた] /(n) loom/EntL1/
+選手権 [せんしゅけん] /(n,adj-no) championship/title (of champion)/EntL1/
+機 [き] /(n) machine/EntL2/
+かな /(n) kana/EntL3/
+刷る [する(P)] /(v5r) to print/EntL4/
+為る [する(P)] /(vs-i) (uk) to do/(2) (uk) to make/EntL5/
+";
+
+    #[test]
+    fn test_parse_edict() {
+        let dict = Dictionary::parse_edict(EDICT);
+        assert_eq!(dict.len(), 8);
+        assert_eq!(dict.get("年").unwrap().readings, vec!["とし", "ねん"]);
+        assert_eq!(dict.get("歳").unwrap().readings, vec!["とし", "とせ"]);
+        assert_eq!(dict.get("年").unwrap().glosses, vec!["year", "age"]);
+        assert_eq!(dict.get("年").unwrap().pos, vec!["n"]);
+        assert_eq!(dict.reading("機"), Some("はた"));
+        assert_eq!(dict.reading("選手権"), Some("せんしゅけん"));
+        assert_eq!(dict.get("選手権").unwrap().pos, vec!["n", "adj-no"]);
+        assert_eq!(
+            dict.get("選手権").unwrap().glosses,
+            vec!["championship", "title (of champion)"]
+        );
+        assert_eq!(dict.reading("かな"), Some("かな"));
+        assert_eq!(dict.reading("？？？？"), None);
+        assert_eq!(dict.lookup_kana("き")[0].word, "機");
+        assert_eq!(dict.glosses("機", "き"), vec!["loom"]);
+        assert_eq!(dict.glosses("木", "き"), vec!["machine"]);
+    }
+
+    #[test]
+    fn test_usually_kana() {
+        let dict = Dictionary::parse_edict(EDICT);
+        assert!(dict.get("為る").unwrap().usually_kana);
+        assert!(!dict.get("刷る").unwrap().usually_kana);
+        assert_eq!(dict.get("為る").unwrap().pos, vec!["vs-i"]);
+        assert_eq!(dict.get("する").unwrap().word, "為る");
+        assert_eq!(dict.glosses("する", "する"), vec!["to do", "to make"]);
+        assert_eq!(dict.glosses("刷る", "する"), vec!["to print"]);
+        assert_eq!(dict.lookup_kana("する").len(), 2);
+
+        // Words with the same reading are not merged.
+        assert_eq!(dict.glosses("擦る", "する"), vec!["to print"]);
+        assert_eq!(dict.get("き"), None);
+
+        let dict = Dictionary::parse_jmdict(JMDICT);
+        assert_eq!(dict.get("こと").unwrap().word, "事");
+        assert_eq!(dict.glosses("こと", "こと"), vec!["thing"]);
+        assert_eq!(dict.get("とし"), None);
+    }
+
+    const JMDICT: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE JMdict [
+<!ENTITY n "noun (common) (futsuumeishi)">
+]>
+<JMdict>
+<entry>
+<ent_seq>1000220</ent_seq>
+<k_ele>
+<keb>明日</keb>
+<ke_pri>ichi1</ke_pri>
+</k_ele>
+<r_ele>
+<reb>あす</reb>
+<re_pri>ichi1</re_pri>
+</r_ele>
+<r_ele>
+<reb>あした</reb>
+<re_pri>ichi1</re_pri>
+</r_ele>
+<r_ele>
+<reb>みょうにち</reb>
+</r_ele>
+<sense>
+<pos>&n;</pos>
+<pos>&adv;</pos>
+<gloss>tomorrow</gloss>
+<gloss xml:lang="ger">morgen</gloss>
+</sense>
+<sense>
+<pos>&n;</pos>
+<gloss>near future</gloss>
+</sense>
+</entry>
+<entry>
+<ent_seq>1000000</ent_seq>
+<k_ele>
+<keb>年</keb>
+</k_ele>
+<k_ele>
+<keb>歳</keb>
+</k_ele>
+<r_ele>
+<reb>とし</reb>
+<re_pri>news1</re_pri>
+</r_ele>
+<r_ele>
+<reb>ねん</reb>
+<re_restr>年</re_restr>
+</r_ele>
+<sense>
+<pos>&n;</pos>
+<gloss>year</gloss>
+<gloss>age</gloss>
+</sense>
+</entry>
+<entry>
+<ent_seq>1313580</ent_seq>
+<k_ele>
+<keb>事</keb>
+</k_ele>
+<r_ele>
+<reb>こと</reb>
+</r_ele>
+<sense>
+<pos>&n;</pos>
+<misc>&uk;</misc>
+<gloss>thing</gloss>
+</sense>
+</entry>
+<entry>
+<ent_seq>1000001</ent_seq>
+<r_ele>
+<reb>ねこ</reb>
+</r_ele>
+<sense>
+<pos>&n;</pos>
+<gloss>cat &amp; kitten</gloss>
+</sense>
+</entry>
+</JMdict>
+"#;
+
+    #[test]
+    fn test_parse_jmdict() {
+        let dict = Dictionary::parse_jmdict(JMDICT);
+        assert_eq!(dict.len(), 5);
+        assert_eq!(
+            dict.get("明日").unwrap().readings,
+            vec!["あす", "あした", "みょうにち"]
+        );
+        assert_eq!(dict.get("明日").unwrap().pos, vec!["n", "adv"]);
+        assert_eq!(
+            dict.get("明日").unwrap().glosses,
+            vec!["tomorrow", "near future"]
+        );
+        assert_eq!(dict.get("年").unwrap().readings, vec!["とし", "ねん"]);
+        assert_eq!(dict.get("歳").unwrap().readings, vec!["とし"]);
+        assert_eq!(dict.reading("ねこ"), Some("ねこ"));
+        assert_eq!(dict.get("ねこ").unwrap().glosses, vec!["cat & kitten"]);
+        assert_eq!(dict.lookup_kana("とし").len(), 2);
+        assert_eq!(dict.glosses("とし", "とし"), vec!["year", "age"]);
+        assert_eq!(
+            dict.glosses("あした", "あした"),
+            vec!["tomorrow", "near future"]
+        );
+        assert!(dict.glosses("いぬ", "いぬ").is_empty());
+    }
+
+    #[test]
+    fn test_write_edict() {
+        let dict = Dictionary::parse_jmdict(JMDICT);
+        let mut out = Vec::new();
+        dict.write_edict(&mut out).unwrap();
+        let text = String::from_utf8(out).unwrap();
+        assert_eq!(
+            text.lines().next().unwrap(),
+            "明日 [あす;あした;みょうにち] /(n,adv) tomorrow/near future/"
+        );
+        assert_eq!(text.lines().last().unwrap(), "ねこ /(n) cat & kitten/");
+
+        let copy = Dictionary::parse_edict(&text);
+        assert_eq!(copy.len(), dict.len());
+        assert_eq!(copy.get("年"), dict.get("年"));
+        assert_eq!(copy.get("こと"), dict.get("こと"));
+    }
+
+    fn word(text: &str) -> Word {
+        Word {
+            word: text.to_string(),
+            count: 1,
+            rank: 1,
+            percentage: 0.0,
+            reading: None,
+            gloss: None,
+            pos: None,
+            generated: false,
+        }
+    }
+
+    #[test]
+    fn test_annotate() {
+        let dict = Dictionary::parse_edict(EDICT);
+        let words = dict.annotate(vec![word("年"), word("かな"), word("犬"), word("する")]);
+        assert_eq!(words[0].reading.as_ref().unwrap(), "とし");
+        assert_eq!(words[0].kana(), "とし");
+        assert_eq!(words[0].gloss.as_ref().unwrap(), "year; age");
+        assert_eq!(words[0].pos.as_ref().unwrap(), "n");
+        assert_eq!(words[1].reading, None);
+        assert_eq!(words[1].kana(), "かな");
+        assert_eq!(words[1].gloss.as_ref().unwrap(), "kana");
+        assert_eq!(words[2].reading, None);
+        assert_eq!(words[2].gloss, None);
+        assert_eq!(words[3].reading, None);
+        assert_eq!(words[3].gloss.as_ref().unwrap(), "to do; to make");
+    }
+}
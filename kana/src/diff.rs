@@ -1,5 +1,3 @@
-use std::collections::HashMap;
-
 use serde::{Deserialize, Serialize};
 
 /// An element in a diff between a source and an input, where the
@@ -11,6 +9,9 @@ use serde::{Deserialize, Serialize};
 pub enum Diff {
     /// Sequence of text is the same in source and input.
     ///
+    /// The text is as given in the input, which may be any of the
+    /// accepted spellings for the source syllable.
+    ///
     /// Operation: just advance both `input` and `source` by the text
     /// length.
     Same(String),
@@ -41,8 +42,90 @@ pub enum Diff {
     Change(String, String),
 }
 
+/// A syllable in the source of a `diff`.
+pub trait Spelling {
+    /// The canonical text for the syllable.
+    fn text(&self) -> &str;
+
+    /// All the accepted spellings for the syllable. Any of those is
+    /// considered the same as `text` in the diff.
+    fn spellings(&self) -> Vec<&str> {
+        vec![self.text()]
+    }
+}
+
+impl Spelling for str {
+    fn text(&self) -> &str {
+        self
+    }
+}
+
+impl Spelling for String {
+    fn text(&self) -> &str {
+        self
+    }
+}
+
+impl<T: Spelling + ?Sized> Spelling for &T {
+    fn text(&self) -> &str {
+        (**self).text()
+    }
+
+    fn spellings(&self) -> Vec<&str> {
+        (**self).spellings()
+    }
+}
+
+/// Maximum number of characters in the input for which the cost
+/// model is used to delete or change text. Longer texts always cost
+/// their length.
+pub const MAX_SIMILAR: usize = 4;
+
+/// Cost of the operations in a `diff`.
+///
+/// By default, the cost of an operation is the length of the text it
+/// touches plus one, so fewer operations are preferred. A model can
+/// make the operations for specific syllables and texts cheaper, to
+/// align the input as a person would mistype it.
+pub trait CostModel {
+    /// Cost of inserting a syllable that is missing in the input.
+    fn insert(&self, syllable: &str) -> usize {
+        syllable.len() + 1
+    }
+
+    /// Cost of deleting extraneous `text` from the input, just before
+    /// the given syllable, if it is less than the cost by length. The
+    /// cost must be at least one.
+    ///
+    /// This is only used for `text` up to `MAX_SIMILAR` characters.
+    fn delete(&self, _text: &str, _next: &str) -> Option<usize> {
+        None
+    }
+
+    /// Cost of changing `text` in the input to the syllable, if it is
+    /// less than the cost by length. The cost must be at least one.
+    ///
+    /// This is only used for `text` up to `MAX_SIMILAR` characters.
+    fn change(&self, _text: &str, _syllable: &str) -> Option<usize> {
+        None
+    }
+}
+
+/// The default `CostModel`, with the cost by length for any kind of
+/// text.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct LengthCost;
+
+impl CostModel for LengthCost {}
+
+/// Returns a diff between the syllables in `source` and the text in
+/// `input`, using `LengthCost`.
+pub fn diff<L: IntoIterator<Item = S>, S: Spelling>(source: L, input: &str) -> Vec<Diff> {
+    diff_with(source, input, &LengthCost)
+}
+
 /// Returns a diff between the syllables in `source` and the text in
-/// `input`.
+/// `input`, using the given cost model.
 ///
 /// The result is a sequence of `Diff` entries, corresponding to each
 /// of the syllables in `source`:
@@ -52,7 +135,12 @@ pub enum Diff {
 /// - `Diff::Delete` is for extraneous text that should be removed
 ///   from the input and as such do not have correspondence in the
 ///   source.
-pub fn diff<'a, L: IntoIterator<Item = S>, S: AsRef<str>>(source: L, input: &'a str) -> Vec<Diff> {
+pub fn diff_with<L, S, C>(source: L, input: &str, model: &C) -> Vec<Diff>
+where
+    L: IntoIterator<Item = S>,
+    S: Spelling,
+    C: CostModel + ?Sized,
+{
     /*
     
     Objective
@@ -119,7 +207,7 @@ pub fn diff<'a, L: IntoIterator<Item = S>, S: AsRef<str>>(source: L, input: &'a
                 1                          // delete B in a single op
             else if B is ""
                 A.len                      // insert each in A
-            else if B.has_prefix(A)
+            else if B.has_prefix(A[0])
                 cost(a + 1, b + A[0].len)  // skip prefix
             else min of
                 ins = 1 + cost(a + 1, b)
@@ -128,7 +216,30 @@ pub fn diff<'a, L: IntoIterator<Item = S>, S: AsRef<str>>(source: L, input: &'a
     
     Then `op` can be defined as just returning the respective
     operation for the minimal cost.
-    
+
+    When the syllable has more than one accepted spelling, `B` can
+    have any of them as prefix. In that case we skip the one with the
+    minimal cost.
+
+    Since `cost(a, b)` only depends on entries with a larger `a` or
+    `b`, we compute it bottom-up in a table, starting from the end of
+    both `A` and `B`, instead of recursively. Only offsets in `B` at a
+    character boundary are computed.
+
+    The minimum for `del` and `rep` does not need to try every `k`:
+
+        min(cost(a, b + k) + k) = min(cost(a, b') + b') - b
+
+    for `b' > b`, so we keep a running minimum of `cost(a, b') + b'`
+    for the current and next rows as we go down `B`. This makes the
+    whole diff `O(A.len * B.len)`.
+
+    The `CostModel` can make the deletion or replacement of short
+    texts cheaper, so those are also tried for `k` up to `MAX_SIMILAR`
+    characters. Since a `Same` may not be the cheapest option with
+    those (e.g. deleting a doubled consonant), it is also compared
+    with the other operations, with precedence on a tie.
+
     The actual cost is more complex than what is depicted above,
     see below for details.
     
@@ -137,139 +248,169 @@ pub fn diff<'a, L: IntoIterator<Item = S>, S: AsRef<str>>(source: L, input: &'a
     #[derive(Copy, Clone, Debug)]
     enum D {
         End,
-        Same,
+        Same(usize),
         Insert,
         Delete(usize),
         Replace(usize),
-    };
-
-    struct Env<'a, S: AsRef<str>> {
-        source: Vec<S>,
-        input: &'a str,
-        memo: HashMap<(usize, usize), (D, usize)>,
-    };
-
-    let mut env = Env {
-        source: source.into_iter().collect(),
-        input: input,
-        memo: HashMap::new(),
-    };
-
-    fn op<'a, S: AsRef<str>>(env: &mut Env<'a, S>, a: usize, b: usize) -> (D, usize) {
-        let key = (a, b);
-        {
-            if let Some(&res) = env.memo.get(&key) {
-                return res;
+    }
+
+    let source: Vec<S> = source.into_iter().collect();
+    let rows = source.len() + 1;
+    let cols = input.len() + 1;
+
+    // Optimal operation and total cost for each `(a, b)`, at the
+    // index `a * cols + b`.
+    let mut table = vec![(D::End, 0); rows * cols];
+
+    // Running minimum of `cost(a, b') + b'` for `b' >= b` and the `b'`
+    // for the minimum, for the current and next rows.
+    let mut row_min = vec![(0, 0); cols];
+    let mut next_min = vec![(0, 0); cols];
+
+    // Offsets in the input at a character boundary. Operations on the
+    // input must always be on a character boundary.
+    let mut offsets: Vec<usize> = input.char_indices().map(|(b, _)| b).collect();
+    offsets.push(input.len());
+
+    // Accepted spellings for each syllable in the source.
+    let spellings: Vec<Vec<&str>> = source.iter().map(|x| x.spellings()).collect();
+
+    // Returns the `D::Same` operation with minimal cost, if any of the
+    // `spellings` for the syllable is a prefix of `rest`. The `next`
+    // row starts at the current offset.
+    fn same(spellings: &[&str], rest: &str, next: &[(D, usize)]) -> Option<(D, usize)> {
+        let mut out: Option<(D, usize)> = None;
+        for &x in spellings {
+            if x.is_empty() || !rest.starts_with(x) {
+                continue;
+            }
+            let (_, cost) = next[x.len()];
+            if out.is_none_or(|(_, c)| cost < c) {
+                out = Some((D::Same(x.len()), cost));
             }
         }
+        out
+    }
 
-        /*
-        How costs are calculated:
-        
-        We calculate cost by the number of characters touched by the
-        operations, so:
-        
-        - Insert the cost is the size of the inserted text
-        - Delete the cost is the size of the deleted text
-        - Change the cost is the size of the deleted + inserted
-        
-        Besides the cost above, we add +1 for each operation. This is
-        so we prioritize the minimal number of operations.
-        */
-
-        let out = if a >= env.source.len() && b >= env.input.len() {
-            // Empty case
-            println!(">> @({}, {}) - END", a, b);
-            (D::End, 0)
-        } else if a >= env.source.len() {
-            // We are at the end of source, so just delete the input
-            // extra suffix in a single operation.
-            let del_len = env.input.len() - b;
-            println!(">> @({}, {}) - DEL", a, b);
-            (D::Delete(del_len), del_len + 1)
-        } else if b >= env.input.len() {
-            // We are at the end of the input, so we append all
-            // remaining syllables in the source, one at a time.
-            let remaining = env.source.len() - a;
-            let mut len = 0;
-            for it in &env.source[a..] {
-                len += it.as_ref().len();
-            }
-            println!(">> @({}, {}) - INS", a, b);
-            (D::Insert, len + remaining)
-        } else if env.input[b..].starts_with(env.source[a].as_ref()) {
-            // Source and input match, skip the syllable and continue.
-            let syllable_len = { env.source[a].as_ref().len() };
-            let (_, cost) = op(env, a + 1, b + syllable_len);
-            println!(">> @({}, {}) - SAME", a, b);
-            (D::Same, cost)
-        } else {
-            let rem_input = { env.input.len() - b };
-            let a_len = env.source[a].as_ref().len();
-
-            // Cost of insertion.
-            let ins = {
-                let (_, cost) = op(env, a + 1, b);
-                (D::Insert, cost + a_len + 1)
-            };
+    // Cost of inserting all syllables in `source[a..]`.
+    let mut suffix_cost = 0;
 
-            // Cost of deletion.
-            let del = {
+    for a in (0..rows).rev() {
+        if a < source.len() {
+            suffix_cost += model.insert(source[a].text());
+        }
+
+        let mut min = (usize::MAX, 0);
+        for &b in offsets.iter().rev() {
+            /*
+            How costs are calculated:
+
+            We calculate cost by the number of characters touched by the
+            operations, so:
+
+            - Insert the cost is the size of the inserted text
+            - Delete the cost is the size of the deleted text
+            - Change the cost is the size of the deleted + inserted
+
+            Besides the cost above, we add +1 for each operation. This is
+            so we prioritize the minimal number of operations.
+
+            The `CostModel` can give a lower cost for inserting a syllable,
+            and for deleting and changing short texts.
+            */
+
+            let out = if a >= source.len() && b >= input.len() {
+                // Empty case
+                (D::End, 0)
+            } else if a >= source.len() {
+                // We are at the end of source, so just delete the input
+                // extra suffix in a single operation.
+                let del_len = input.len() - b;
+                (D::Delete(del_len), del_len + 1)
+            } else if b >= input.len() {
+                // We are at the end of the input, so we append all
+                // remaining syllables in the source, one at a time.
+                (D::Insert, suffix_cost)
+            } else {
+                let syllable = source[a].text();
+                let a_len = syllable.len();
+
+                // Cost of insertion.
+                let ins = {
+                    let (_, cost) = table[(a + 1) * cols + b];
+                    (D::Insert, cost + model.insert(syllable))
+                };
+
+                // Length of the first character in the input.
+                let step = input[b..].chars().next().unwrap().len_utf8();
+
+                // Cost of deletion.
                 let mut del = {
-                    let (_, cost) = op(env, a, b + 1);
-                    (1, cost + 1)
+                    let (cost, end) = row_min[b + step];
+                    (D::Delete(end - b), cost - b + 1)
                 };
-                for k in 2..rem_input + 1 {
-                    let (_, new_cost) = op(env, a, b + k);
-                    let new_cost = new_cost + k;
-                    if new_cost < del.1 {
-                        del = (k, new_cost);
-                    }
-                }
-                (D::Delete(del.0), del.1 + 1)
-            };
 
-            // Cost of replace.
-            let rep = {
+                // Cost of replace.
                 let mut rep = {
-                    let (_, cost) = op(env, a + 1, b + 1);
-                    (1, cost + 1 + a_len)
+                    let (cost, end) = next_min[b + step];
+                    (D::Replace(end - b), cost - b + a_len + 1)
                 };
-                for k in 2..rem_input + 1 {
-                    let (_, new_cost) = op(env, a + 1, b + k);
-                    let new_cost = new_cost + k + a_len;
-                    if new_cost < rep.1 {
-                        rep = (k, new_cost);
+
+                let same = same(&spellings[a], &input[b..], &table[(a + 1) * cols + b..]);
+
+                // Cost of deleting or replacing short texts, given by
+                // the model. Since any operation costs at least one, the
+                // model is only used when it can improve the best cost.
+                let best = [ins.1, del.1, rep.1, same.map_or(usize::MAX, |x| x.1)];
+                let best = best.iter().cloned().min().unwrap();
+                let ends = input[b..].char_indices().skip(1).map(|(k, _)| b + k);
+                for end in ends.chain(Some(input.len())).take(MAX_SIMILAR) {
+                    let text = &input[b..end];
+                    let (_, next) = table[a * cols + end];
+                    if next < best && next + 1 < del.1 {
+                        if let Some(cost) = model.delete(text, syllable) {
+                            if cost + next < del.1 {
+                                del = (D::Delete(end - b), cost + next);
+                            }
+                        }
+                    }
+                    let (_, next) = table[(a + 1) * cols + end];
+                    if next < best && next + 1 < rep.1 {
+                        if let Some(cost) = model.change(text, syllable) {
+                            if cost + next < rep.1 {
+                                rep = (D::Replace(end - b), cost + next);
+                            }
+                        }
                     }
                 }
-                (D::Replace(rep.0), rep.1 + 1)
-            };
-
-            println!(
-                ">> @({}, {}) - INS / DEL / REP: {:?} / {:?} / {:?}",
-                a, b, ins, del, rep
-            );
 
-            // Minimize the cost. Precedence order is REP > DEL > INS
-            if ins.1 < del.1 {
-                if ins.1 < rep.1 {
-                    ins
-                } else {
-                    rep
+                // Minimize the cost. Precedence order is
+                // SAME > REP > DEL > INS
+                let mut out = rep;
+                if let Some(same) = same {
+                    if same.1 <= out.1 {
+                        out = same;
+                    }
                 }
-            } else {
-                if del.1 < rep.1 {
-                    del
-                } else {
-                    rep
+                if del.1 < out.1 {
+                    out = del;
                 }
-            }
-        };
+                if ins.1 < out.1 {
+                    out = ins;
+                }
+                out
+            };
 
-        println!("== @({}, {}) = {:?}", a, b, out);
+            table[a * cols + b] = out;
 
-        env.memo.insert(key, out);
-        out
+            // On a tie, the shortest deletion or replacement wins.
+            if out.1 + b <= min.0 {
+                min = (out.1 + b, b);
+            }
+            row_min[b] = min;
+        }
+
+        std::mem::swap(&mut row_min, &mut next_min);
     }
 
     let mut out = Vec::new();
@@ -277,30 +418,30 @@ pub fn diff<'a, L: IntoIterator<Item = S>, S: AsRef<str>>(source: L, input: &'a
     let mut a = 0;
     let mut b = 0;
     'main: loop {
-        let (next, _) = { op(&mut env, a, b) };
+        let (next, _) = table[a * cols + b];
         match next {
             D::End => {
                 break 'main;
             }
-            D::Same => {
-                let s = env.source[a].as_ref();
+            D::Same(n) => {
+                let s = &input[b..b + n];
                 out.push(Diff::Same(s.to_string()));
-                b += s.len();
+                b += n;
                 a += 1;
             }
             D::Insert => {
-                let s = env.source[a].as_ref();
+                let s = source[a].text();
                 out.push(Diff::Insert(s.to_string()));
                 a += 1;
             }
             D::Delete(n) => {
-                let s = &env.input[b..b + n];
+                let s = &input[b..b + n];
                 out.push(Diff::Delete(s.to_string()));
                 b += s.len();
             }
             D::Replace(n) => {
-                let s1 = &env.input[b..b + n];
-                let s2 = env.source[a].as_ref();
+                let s1 = &input[b..b + n];
+                let s2 = source[a].text();
                 out.push(Diff::Change(s1.to_string(), s2.to_string()));
                 a += 1;
                 b += s1.len();
@@ -336,6 +477,32 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_diff_spellings() {
+        struct Alt(&'static str, &'static str);
+        impl Spelling for Alt {
+            fn text(&self) -> &str {
+                self.0
+            }
+            fn spellings(&self) -> Vec<&str> {
+                vec![self.0, self.1]
+            }
+        }
+
+        let src = vec![Alt("shi", "si"), Alt("n", "nn"), Alt("ni", "ni")];
+        assert_eq!(diff(&src, "shinni"), vec![s("shi"), s("n"), s("ni")]);
+        assert_eq!(diff(&src, "sinnni"), vec![s("si"), s("nn"), s("ni")]);
+        assert_eq!(diff(&src, "sxnni"), vec![c("sx", "shi"), s("n"), s("ni")]);
+    }
+
+    #[test]
+    fn test_diff_unicode() {
+        let src = vec!["ka", "ta"];
+        assert_eq!(diff(&src, "kāta"), vec![c("kā", "ka"), s("ta")]);
+        assert_eq!(diff(&src, "かta"), vec![c("か", "ka"), s("ta")]);
+        assert_eq!(diff(&src, "kaたta"), vec![s("ka"), d("た"), s("ta")]);
+    }
+
     #[test]
     fn test_diff_empty() {
         // Empty source and input
@@ -421,6 +588,37 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_diff_long() {
+        // Long inputs must not be limited by the stack.
+        let src: Vec<_> = (0..500).map(|x| format!("{}", x % 10)).collect();
+        let input: String = src.iter().map(|x| x.replace("5", "x")).collect();
+        let result = diff(&src, &input);
+        assert_eq!(result.len(), 500);
+        assert_eq!(result[4], s("4"));
+        assert_eq!(result[5], c("x", "5"));
+    }
+
+    #[test]
+    fn test_diff_cost_model() {
+        // Changing to a lowercase letter is cheap.
+        struct CaseCost;
+        impl CostModel for CaseCost {
+            fn change(&self, text: &str, syllable: &str) -> Option<usize> {
+                if text.to_lowercase() == syllable {
+                    Some(1)
+                } else {
+                    None
+                }
+            }
+        }
+
+        let src = vec!["abc"];
+        assert_eq!(diff(&src, "xABC"), vec![c("xABC", "abc")]);
+        assert_eq!(diff_with(&src, "xABC", &CaseCost), vec![d("x"), c("ABC", "abc")]);
+        assert_eq!(diff_with(&src, "xyz", &CaseCost), vec![c("xyz", "abc")]);
+    }
+
     fn s(txt: &str) -> Diff {
         Diff::Same(String::from(txt))
     }
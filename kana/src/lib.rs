@@ -2,9 +2,12 @@
 extern crate lazy_static;
 
 extern crate rand;
+extern crate rand_hc;
 extern crate regex;
 extern crate serde;
 
+mod list;
+
 mod words;
 pub use words::*;
 
@@ -14,10 +17,35 @@ pub use romaji::*;
 mod split;
 pub use split::*;
 
+mod convert;
+pub use convert::*;
+
+mod normalize;
+pub use normalize::*;
+
+mod pseudo;
+pub use pseudo::*;
+
+mod dict;
+pub use dict::*;
+
 mod wordset;
 pub use wordset::*;
 
 mod diff;
-pub use diff::Diff;
+pub use diff::{diff, diff_with, CostModel, Diff, LengthCost, Spelling, MAX_SIMILAR};
+
+mod cost;
+pub use cost::*;
+
+mod mistake;
+pub use mistake::Mistake;
+
+mod typo;
+pub use typo::{is_typo, ErrorKind, Layout};
 
 mod tables;
+pub use tables::{
+    gojuon, Category, Column, DigraphPrefix, DigraphSuffix, GojuonEntry, Kana, LongVowels,
+    RomanizationSystem, Row, Script, Voicing,
+};
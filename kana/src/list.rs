@@ -0,0 +1,200 @@
+// Parser for the word list format of `words.txt`.
+//
+// This module has no dependencies on the rest of the crate since it
+// is also used by `build.rs` to embed the word list.
+
+/// Entry parsed from a word list.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Entry {
+    pub rank: u32,
+    pub word: String,
+    pub count: i32,
+    pub percentage: f64,
+    pub reading: Option<String>,
+    pub gloss: Option<String>,
+    pub pos: Option<String>,
+}
+
+/// Parses a word list in the format of `words.txt`.
+///
+/// The first line is a header with the column names. The `Rank`,
+/// `Word` and `Occurrences` columns are required, and are assumed to
+/// be the first three if the header does not name them. The
+/// `Percentage`, `Reading`, `Gloss` and `POS` columns are optional,
+/// and any other column is ignored. If there is no `Percentage`, it
+/// is computed from the occurrences.
+///
+/// Columns are separated by tabs, if the header has any, or commas.
+/// Fields can be quoted with `"` to include the separator.
+///
+/// Returns the line number and a description on error.
+pub fn parse_list(text: &str) -> Result<Vec<Entry>, (usize, String)> {
+    let mut lines = text.lines().enumerate();
+    let header = match lines.next() {
+        Some((_, header)) => header,
+        None => return Ok(Vec::new()),
+    };
+
+    let separator = if header.contains('\t') { '\t' } else { ',' };
+    let header: Vec<String> = split_line(header, separator)
+        .iter()
+        .map(|x| x.to_lowercase().replace(|c: char| !c.is_alphanumeric(), ""))
+        .collect();
+    let column = |names: &[&str]| header.iter().position(|x| names.contains(&x.as_str()));
+
+    let rank_col = column(&["rank"]).unwrap_or(0);
+    let word_col = column(&["word"]).unwrap_or(1);
+    let count_col = column(&["occurrences", "count"]).unwrap_or(2);
+    let percentage_col = column(&["percentage", "percent"]);
+    let reading_col = column(&["reading"]);
+    let gloss_col = column(&["gloss", "meaning", "english"]);
+    let pos_col = column(&["pos", "partofspeech"]);
+
+    let mut entries = Vec::new();
+    let mut has_percentage = true;
+    for (index, line) in lines {
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let line_number = index + 1;
+        let fields = split_line(line, separator);
+        let field = |col: Option<usize>| {
+            col.and_then(|x| fields.get(x))
+                .map(|x| x.trim())
+                .filter(|x| !x.is_empty())
+        };
+        let invalid = |name: &str| (line_number, format!("invalid {}", name));
+
+        let rank = field(Some(rank_col))
+            .and_then(|x| x.parse::<u32>().ok())
+            .ok_or_else(|| invalid("rank"))?;
+        let word = field(Some(word_col)).ok_or_else(|| invalid("word"))?;
+        let count = field(Some(count_col))
+            .and_then(|x| x.parse::<i32>().ok())
+            .ok_or_else(|| invalid("occurrences"))?;
+        let percentage = match field(percentage_col) {
+            Some(text) => text
+                .trim_end_matches('%')
+                .trim()
+                .parse::<f64>()
+                .ok()
+                .filter(|x| x.is_finite())
+                .ok_or_else(|| invalid("percentage"))?,
+            None => {
+                has_percentage = false;
+                0.0
+            }
+        };
+
+        let text = |col: Option<usize>| field(col).map(|x| x.to_string());
+        entries.push(Entry {
+            rank,
+            word: word.to_string(),
+            count,
+            percentage,
+            reading: text(reading_col),
+            gloss: text(gloss_col),
+            pos: text(pos_col),
+        });
+    }
+
+    if !has_percentage {
+        let total: f64 = entries.iter().map(|x| x.count as f64).sum();
+        for it in entries.iter_mut() {
+            it.percentage = if total > 0.0 {
+                100.0 * it.count as f64 / total
+            } else {
+                0.0
+            };
+        }
+    }
+
+    Ok(entries)
+}
+
+/// Splits a line in fields, handling quoted fields.
+fn split_line(line: &str, separator: char) -> Vec<String> {
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = line.chars().peekable();
+    while let Some(chr) = chars.next() {
+        match chr {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            '"' if quoted || field.trim().is_empty() => {
+                quoted = !quoted;
+                if quoted {
+                    field.clear();
+                }
+            }
+            _ if chr == separator && !quoted => {
+                fields.push(field);
+                field = String::new();
+            }
+            _ => field.push(chr),
+        }
+    }
+    fields.push(field);
+    fields
+}
+
+// spell-checker: disable
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_list() {
+        let text = "Rank,Word,Occurrences,Percentage,Cumulative%\n1,する,138279,7.34511%,7.345%\n";
+        let entries = parse_list(text).unwrap();
+        assert_eq!(
+            entries,
+            vec![Entry {
+                rank: 1,
+                word: "する".to_string(),
+                count: 138279,
+                percentage: 7.34511,
+                reading: None,
+                gloss: None,
+                pos: None,
+            }]
+        );
+    }
+
+    #[test]
+    fn test_parse_list_optional() {
+        let text = "Word,Rank,POS,Occurrences,Gloss,Reading\n\
+                    寿司,1,noun,3,\"sushi, vinegared rice\",すし\n\
+                    食べる,2,verb,1,\"to \"\"eat\"\"\",たべる\n\
+                    てんぷら,3,,0,,\n";
+        let entries = parse_list(text).unwrap();
+        assert_eq!(entries.len(), 3);
+        assert_eq!(entries[0].word, "寿司");
+        assert_eq!(entries[0].reading.as_ref().unwrap(), "すし");
+        assert_eq!(entries[0].gloss.as_ref().unwrap(), "sushi, vinegared rice");
+        assert_eq!(entries[0].pos.as_ref().unwrap(), "noun");
+        assert_eq!(entries[0].percentage, 75.0);
+        assert_eq!(entries[1].gloss.as_ref().unwrap(), "to \"eat\"");
+        assert_eq!(entries[1].percentage, 25.0);
+        assert_eq!(entries[2].reading, None);
+        assert_eq!(entries[2].pos, None);
+    }
+
+    #[test]
+    fn test_parse_list_invalid() {
+        assert_eq!(
+            parse_list("Rank,Word,Occurrences\n1,する,x\n"),
+            Err((2, "invalid occurrences".to_string()))
+        );
+        assert_eq!(
+            parse_list("Rank,Word,Occurrences\n\nx,する,1\n"),
+            Err((3, "invalid rank".to_string()))
+        );
+        assert_eq!(parse_list(""), Ok(Vec::new()));
+    }
+}
@@ -0,0 +1,237 @@
+use serde::{Deserialize, Serialize};
+
+use diff::Diff;
+use split::{tokenize, Syllable, SyllableKind};
+use tables::{Kana, LongVowels, RomanizationSystem, Voicing};
+
+/// Pairs of kana that look alike and are easily confused. Pairs with
+/// one kana in each script are used for `Mistake::WrongScript`.
+///
+/// Voiced kana are not listed, as they are compared without the
+/// dakuten (e.g. `ジ` and `ヅ` are found from `シ` and `ツ`).
+const LOOK_ALIKE: &[(char, char)] = &[
+    // Hiragana
+    ('ぬ', 'め'),
+    ('ね', 'れ'),
+    ('ね', 'わ'),
+    ('れ', 'わ'),
+    ('る', 'ろ'),
+    ('さ', 'ち'),
+    ('き', 'さ'),
+    ('は', 'ほ'),
+    ('け', 'は'),
+    ('い', 'り'),
+    ('あ', 'お'),
+    ('た', 'な'),
+    ('ま', 'も'),
+    // Katakana
+    ('シ', 'ツ'),
+    ('ソ', 'ン'),
+    ('シ', 'ン'),
+    ('ソ', 'ツ'),
+    ('ク', 'ケ'),
+    ('ク', 'タ'),
+    ('ク', 'ワ'),
+    ('フ', 'ワ'),
+    ('ウ', 'ワ'),
+    ('ス', 'ヌ'),
+    ('コ', 'ユ'),
+    ('エ', 'ユ'),
+    ('チ', 'テ'),
+    ('キ', 'チ'),
+    ('ナ', 'メ'),
+    ('マ', 'ア'),
+    ('ル', 'レ'),
+    ('ヲ', 'ラ'),
+    // Across scripts
+    ('こ', 'ニ'),
+    ('し', 'レ'),
+    ('い', 'リ'),
+    ('う', 'ラ'),
+    ('く', 'ヘ'),
+];
+
+/// Label for a mistake in a `Match`, explaining why the answer was
+/// wrong so that the UI can show targeted feedback.
+///
+/// The kana in `Dakuten`, `LookAlike` and `WrongScript` is the one the
+/// answer was read as.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Mistake {
+    /// The kana was confused with the same kana with a different
+    /// dakuten or handakuten (e.g. `が` read as `か`, or `ぱ` as `ば`).
+    Dakuten(char),
+
+    /// The kana was confused with a similar looking kana (e.g. `シ`
+    /// read as `ツ`).
+    LookAlike(char),
+
+    /// A `っ` was not read by doubling the next consonant.
+    MissedSokuon,
+
+    /// A long vowel (`ー` or a hiragana vowel) was not read.
+    MissedLongVowel,
+
+    /// A yōon was read as two full syllables (e.g. `kiya` for `きゃ`).
+    SplitYoon,
+
+    /// The kana was read as a similar looking kana from the other
+    /// script (e.g. `ニ` read as `こ`).
+    WrongScript(char),
+}
+
+/// Labels the mistakes in a diff between the romaji and the syllables
+/// for the normalized kana in `split` (see `Match`). Returns one entry
+/// for each entry in `diff`, with `None` for `Diff::Same` and for any
+/// mistake that could not be explained.
+pub(crate) fn find_mistakes(
+    split: &[char],
+    syllables: &[Syllable],
+    diff: &[Diff],
+) -> Vec<Option<Mistake>> {
+    let mut result = Vec::new();
+    let mut kana_index = 0;
+    let mut split_yoon = false;
+    for it in diff {
+        let mistake = match it {
+            Diff::Same(_) => {
+                kana_index += 1;
+                None
+            }
+            Diff::Change(text, _) => {
+                let mistake = if split_yoon && is_yoon_suffix(split, syllables, kana_index) {
+                    Some(Mistake::SplitYoon)
+                } else {
+                    find_confusion(split, kana_index, text)
+                };
+                kana_index += 1;
+                mistake
+            }
+            Diff::Insert(_) => {
+                let mistake = match syllables[kana_index].kind {
+                    SyllableKind::Sokuon => Some(Mistake::MissedSokuon),
+                    SyllableKind::LongVowel => Some(Mistake::MissedLongVowel),
+                    _ => None,
+                };
+                kana_index += 1;
+                mistake
+            }
+            Diff::Delete(text) => {
+                // The `i` from reading the first kana of a yōon as a
+                // full syllable (e.g. `ki` in `kiya`).
+                let is_yoon = kana_index > 0
+                    && text == "i"
+                    && syllables[kana_index - 1].kind == SyllableKind::Digraph
+                    && is_yoon_suffix(split, syllables, kana_index);
+                if is_yoon {
+                    Some(Mistake::SplitYoon)
+                } else {
+                    None
+                }
+            }
+        };
+        split_yoon = mistake == Some(Mistake::SplitYoon);
+        result.push(mistake);
+    }
+    result
+}
+
+/// True if the kana at `index` is the small kana of a yōon.
+fn is_yoon_suffix(split: &[char], syllables: &[Syllable], index: usize) -> bool {
+    let is_suffix = split
+        .get(index)
+        .and_then(|&x| Kana::get(x))
+        .is_some_and(|x| x.is_digraph_suffix());
+    is_suffix && syllables[index].kind == SyllableKind::Digraph
+}
+
+/// Finds a kana that the kana at `index` was confused with, given the
+/// text that was read for it.
+fn find_confusion(split: &[char], index: usize, text: &str) -> Option<Mistake> {
+    let kana = Kana::get(split[index])?;
+
+    let base = kana.without_dakuten();
+    let voicing = vec![Some(base), base.with_dakuten(), base.with_handakuten()];
+    for other in voicing.into_iter().flatten().filter(|&x| x != kana) {
+        if reads_as(split, index, other, text) {
+            return Some(Mistake::Dakuten(other.get_char()));
+        }
+    }
+
+    let base = base.get_char();
+    let similar = LOOK_ALIKE.iter().filter_map(|&(a, b)| {
+        if a == base {
+            Kana::get(b)
+        } else if b == base {
+            Kana::get(a)
+        } else {
+            None
+        }
+    });
+    for other in similar {
+        let other = match kana.voicing() {
+            Voicing::Unvoiced => Some(other),
+            Voicing::Voiced => other.with_dakuten(),
+            Voicing::SemiVoiced => other.with_handakuten(),
+        };
+        if let Some(other) = other.filter(|&x| reads_as(split, index, x, text)) {
+            let chr = other.get_char();
+            return Some(if other.script() == kana.script() {
+                Mistake::LookAlike(chr)
+            } else {
+                Mistake::WrongScript(chr)
+            });
+        }
+    }
+
+    None
+}
+
+/// True if `text` is an accepted spelling for the kana at `index` once
+/// it is replaced by `other`.
+fn reads_as(split: &[char], index: usize, other: Kana, text: &str) -> bool {
+    let mut chars = split.to_vec();
+    chars[index] = other.get_char();
+    let chars: String = chars.into_iter().collect();
+    let syllables = tokenize(&chars, RomanizationSystem::default(), LongVowels::default());
+    syllables
+        .get(index)
+        .is_some_and(|x| x.accepted.iter().any(|x| x == text))
+}
+
+// spell-checker: disable
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use romaji::Match;
+
+    #[test]
+    fn test_find_mistakes() {
+        use super::Mistake::*;
+
+        let labels = |kana: &str, romaji: &str| -> Vec<Mistake> {
+            let m = Match::new(kana, romaji);
+            assert_eq!(m.mistakes.len(), m.diff.len());
+            m.mistakes.into_iter().flatten().collect()
+        };
+
+        assert_eq!(labels("かば", "kaba"), vec![]);
+        assert_eq!(labels("ぱん", "han"), vec![Dakuten('は')]);
+        assert_eq!(labels("はは", "papa"), vec![Dakuten('ぱ'), Dakuten('ぱ')]);
+        assert_eq!(labels("ぎゃ", "kya"), vec![Dakuten('き')]);
+        assert_eq!(labels("シ", "tsu"), vec![LookAlike('ツ')]);
+        assert_eq!(labels("ソ", "n"), vec![LookAlike('ン')]);
+        assert_eq!(labels("ぬ", "me"), vec![LookAlike('め')]);
+        assert_eq!(labels("ジ", "zu"), vec![LookAlike('ヅ')]);
+        assert_eq!(labels("ニ", "ko"), vec![WrongScript('こ')]);
+        assert_eq!(labels("し", "re"), vec![WrongScript('レ')]);
+        assert_eq!(labels("がっこう", "gakou"), vec![MissedSokuon]);
+        assert_eq!(labels("ラーメン", "ramen"), vec![MissedLongVowel]);
+        assert_eq!(labels("しゃ", "shiya"), vec![SplitYoon, SplitYoon]);
+        assert_eq!(labels("ねこ", "neto"), vec![]);
+
+        let m = Match::new("きゃく", "kiyaku");
+        assert_eq!(m.mistakes, vec![None, Some(SplitYoon), None, None]);
+    }
+}
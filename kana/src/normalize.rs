@@ -0,0 +1,113 @@
+use std::ops::Range;
+
+use tables::{semivoiced, voiced};
+
+/// Half-width katakana and punctuation, in the same order as
+/// `FULL_WIDTH`.
+const HALF_WIDTH: &str = "｡｢｣､･ｦｧｨｩｪｫｬｭｮｯｰｱｲｳｴｵｶｷｸｹｺｻｼｽｾｿﾀﾁﾂﾃﾄﾅﾆﾇﾈﾉﾊﾋﾌﾍﾎﾏﾐﾑﾒﾓﾔﾕﾖﾗﾘﾙﾚﾛﾜﾝ";
+
+/// Full-width equivalents for `HALF_WIDTH`.
+const FULL_WIDTH: &str = "。「」、・ヲァィゥェォャュョッーアイウエオカキクケコサシスセソタチツテトナニヌネノハヒフヘホマミムメモヤユヨラリルレロワン";
+
+/// Text normalized by `normalize`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Normalized {
+    /// The normalized text.
+    pub text: String,
+
+    /// For each character in `text`, the range of characters in the
+    /// original text that generated it.
+    pub source: Vec<Range<usize>>,
+}
+
+/// Normalizes the alternative forms of kana and romaji text, so that
+/// they can be handled by the conversion functions:
+///
+/// - Half-width katakana (e.g. `ｶﾀｶﾅ`) is converted to full-width.
+/// - Dakuten and handakuten written as separate characters, either
+///   half-width (`ﾞ` and `ﾟ`), combining (U+3099 and U+309A) or
+///   spacing (`゛` and `゜`), are composed with the previous kana
+///   (e.g. `ｶﾞ` is `ガ`).
+/// - Full-width ASCII, as typed by an IME (e.g. `ｋａｎａ`), and the
+///   ideographic space are converted to ASCII.
+///
+/// Any other character is kept as is.
+pub fn normalize(text: &str) -> Normalized {
+    let mut out: Vec<char> = Vec::new();
+    let mut source: Vec<Range<usize>> = Vec::new();
+    for (index, chr) in text.chars().enumerate() {
+        // Dakuten and handakuten marks modify the previous kana, if
+        // it has a voiced form.
+        let mark: Option<fn(char) -> char> = match chr {
+            '\u{3099}' | '゛' | 'ﾞ' => Some(voiced),
+            '\u{309A}' | '゜' | 'ﾟ' => Some(semivoiced),
+            _ => None,
+        };
+        if let (Some(mark), Some(&last)) = (mark, out.last()) {
+            if mark(last) != last {
+                *out.last_mut().unwrap() = mark(last);
+                source.last_mut().unwrap().end = index + 1;
+                continue;
+            }
+        }
+
+        let chr = match chr {
+            'ﾞ' => '゛',
+            'ﾟ' => '゜',
+            '\u{3000}' => ' ',
+            '\u{FF01}'..='\u{FF5E}' => std::char::from_u32(chr as u32 - 0xFEE0).unwrap(),
+            _ => match HALF_WIDTH.chars().position(|x| x == chr) {
+                Some(pos) => FULL_WIDTH.chars().nth(pos).unwrap(),
+                None => chr,
+            },
+        };
+        out.push(chr);
+        source.push(index..index + 1);
+    }
+
+    Normalized {
+        text: out.into_iter().collect(),
+        source,
+    }
+}
+
+// spell-checker: disable
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn text(input: &str) -> String {
+        normalize(input).text
+    }
+
+    #[test]
+    fn test_normalize_half_width() {
+        assert_eq!(text("ｶﾀｶﾅ"), "カタカナ");
+        assert_eq!(text("ｶﾞｷﾞﾊﾟｳﾞ"), "ガギパヴ");
+        assert_eq!(text("ｷｬｯﾁｰ｡"), "キャッチー。");
+        assert_eq!(text("ﾞｱﾞ"), "゛ア゛");
+    }
+
+    #[test]
+    fn test_normalize_dakuten() {
+        assert_eq!(text("か\u{3099}き\u{3099}"), "がぎ");
+        assert_eq!(text("は\u{309A}ホ\u{309A}"), "ぱポ");
+        assert_eq!(text("か゛は゜"), "がぱ");
+        assert_eq!(text("が\u{3099}"), "が\u{3099}");
+    }
+
+    #[test]
+    fn test_normalize_full_width() {
+        assert_eq!(text("ｋａｎａ"), "kana");
+        assert_eq!(text("ＫＡＮＡ　１２３！"), "KANA 123!");
+        assert_eq!(text("かな abc"), "かな abc");
+    }
+
+    #[test]
+    fn test_normalize_source() {
+        let result = normalize("ｶﾞｷ\u{3099}x");
+        assert_eq!(result.text, "ガギx");
+        assert_eq!(result.source, vec![0..2, 2..4, 4..5]);
+    }
+}
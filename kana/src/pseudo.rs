@@ -0,0 +1,210 @@
+use rand::{seq::SliceRandom, Rng};
+
+use split::try_split_romaji;
+use tables::{gojuon, Category, Column, Kana, LongVowels, RomanizationSystem, Row, Script};
+
+/// Maximum number of morae in a pseudo-word, not counting the
+/// required one.
+const MAX_FILLER: usize = 3;
+
+/// Number of random attempts before giving up on a pseudo-word.
+const MAX_ATTEMPTS: usize = 100;
+
+/// Generates a pronounceable pseudo-word containing `required`, using
+/// the other characters in `charset` as filler where possible.
+///
+/// The word is a random sequence of morae in the script of `required`
+/// following the rules for kana spelling (e.g. `ん` never starts a
+/// word, `っ` is followed by a consonant and small `ゃゅょ` only
+/// follow the `i` column). If `charset` has no filler for the script,
+/// the basic gojūon table is used instead.
+///
+/// Returns `None` if `required` is not kana or no valid word can be
+/// made with it.
+pub fn pseudo_word<R: Rng>(rng: &mut R, required: char, charset: &str) -> Option<String> {
+    let kana = Kana::get(required)?;
+    let script = kana.script();
+
+    let mut filler = morae(charset, script);
+    if filler.is_empty() {
+        filler = morae(&basic(script), script);
+    }
+
+    // The mora that contains the required character.
+    let (head, is_mark) = match kana {
+        // Yōon are the only digraphs we generate.
+        Kana::Small(..) if kana.to_large().row() == Some(Row::Ya) => {
+            let mut bases: Vec<_> = filler
+                .iter()
+                .filter(|x| is_digraph_base(x))
+                .cloned()
+                .collect();
+            if bases.is_empty() {
+                bases = morae(&basic(script), script);
+                bases.retain(|x| is_digraph_base(x));
+            }
+            let base = bases.choose(rng)?;
+            (format!("{}{}", base, required), false)
+        }
+        Kana::Small(..) => return None,
+        Kana::SmallTsu(_) | Kana::Bar(_) => (String::new(), true),
+        _ => (required.to_string(), false),
+    };
+
+    for _ in 0..MAX_ATTEMPTS {
+        let count = rng.gen_range(1, MAX_FILLER + 1);
+        let mut word: Vec<String> = (0..count)
+            .map(|_| filler.choose(rng).unwrap().clone())
+            .collect();
+        if !is_mark {
+            let index = rng.gen_range(0, word.len() + 1);
+            word.insert(index, head.clone());
+        } else {
+            // Sokuon and long vowels modify a previous mora.
+            let index = rng.gen_range(1, word.len() + 1);
+            word.insert(index, required.to_string());
+        }
+
+        let word = word.concat();
+        if is_valid(&word) {
+            return Some(word);
+        }
+    }
+    None
+}
+
+/// Returns the basic gojūon characters in the given script.
+fn basic(script: Script) -> String {
+    gojuon()
+        .iter()
+        .filter(|x| x.category == Category::Basic)
+        .map(|x| x.kana(script))
+        .collect()
+}
+
+/// Returns the morae from `charset` that can be used as filler in
+/// the given script: any large kana, but not `ん` or the marks.
+fn morae(charset: &str, script: Script) -> Vec<String> {
+    let mut out: Vec<String> = Vec::new();
+    for chr in charset.chars() {
+        let kana = match Kana::get(chr) {
+            Some(kana) => kana,
+            None => continue,
+        };
+        let usable = match kana {
+            Kana::Chr(..) | Kana::Dig(..) => !kana.is_small() && kana.row() != Some(Row::N),
+            _ => false,
+        };
+        if usable && kana.script() == script && !out.iter().any(|x| x.starts_with(chr)) {
+            out.push(chr.to_string());
+        }
+    }
+    out
+}
+
+/// True if the mora can be followed by a small `ゃゅょ`.
+fn is_digraph_base(mora: &str) -> bool {
+    let kana = mora.chars().next().and_then(Kana::get);
+    kana.is_some_and(|x| {
+        x.column() == Some(Column::I) && !matches!(x.row(), Some(Row::A) | Some(Row::Wa))
+    })
+}
+
+/// Checks the spelling rules for a generated word.
+fn is_valid(word: &str) -> bool {
+    let system = RomanizationSystem::default();
+    let split = match try_split_romaji(word, system, LongVowels::default()) {
+        Ok(split) => split,
+        Err(_) => return false,
+    };
+
+    let mut chars = word.chars().peekable();
+    let mut last = None;
+    while let Some(chr) = chars.next() {
+        let kana = Kana::get(chr);
+        let next = chars.peek().cloned().and_then(Kana::get);
+        let valid = match kana {
+            // The syllabic `n` and the marks never start a word.
+            Some(kana) if kana.row() == Some(Row::N) => last.is_some(),
+            Some(Kana::Bar(_)) => match last {
+                Some(Kana::Chr(..)) | Some(Kana::Dig(..)) | Some(Kana::Small(..)) => {
+                    last.and_then(|x| x.row()) != Some(Row::N)
+                }
+                _ => false,
+            },
+            // The sokuon must double a consonant.
+            Some(Kana::SmallTsu(_)) => {
+                last.is_some()
+                    && next.and_then(|x| x.row()).is_some_and(|x| {
+                        !matches!(
+                            x,
+                            Row::A | Row::Na | Row::Ma | Row::Ya | Row::Ra | Row::Wa | Row::N
+                        )
+                    })
+            }
+            _ => true,
+        };
+        if !valid {
+            return false;
+        }
+        last = kana;
+    }
+    !split.is_empty()
+}
+
+// spell-checker: disable
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use rand::{rngs::StdRng, SeedableRng};
+
+    fn words(required: char, charset: &str) -> Vec<String> {
+        let mut rng = StdRng::seed_from_u64(0);
+        (0..50)
+            .map(|_| pseudo_word(&mut rng, required, charset).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_pseudo_word() {
+        for it in words('ぢ', "あかさ") {
+            assert!(it.contains('ぢ'), "{}", it);
+            assert!(it.chars().all(|x| "ぢあかさ".contains(x)), "{}", it);
+        }
+        for it in words('ヅ', "") {
+            assert!(it.contains('ヅ'), "{}", it);
+            assert!(it
+                .chars()
+                .all(|x| Kana::get(x).unwrap().script() == Script::Katakana));
+        }
+    }
+
+    #[test]
+    fn test_pseudo_word_rules() {
+        for it in words('ん', "なに") {
+            assert!(!it.starts_with('ん'), "{}", it);
+        }
+        for it in words('っ', "かあ") {
+            assert!(it.contains("っか"), "{}", it);
+            assert!(!it.starts_with('っ') && !it.ends_with('っ'), "{}", it);
+        }
+        for it in words('ー', "カア") {
+            assert!(!it.starts_with('ー'), "{}", it);
+        }
+        for it in words('ゃ', "かきあ") {
+            assert!(it.contains("きゃ"), "{}", it);
+        }
+        for it in words('ャ', "カ") {
+            assert!(it.contains("ャ"), "{}", it);
+        }
+    }
+
+    #[test]
+    fn test_pseudo_word_invalid() {
+        let mut rng = StdRng::seed_from_u64(0);
+        assert_eq!(pseudo_word(&mut rng, 'x', "あ"), None);
+        assert_eq!(pseudo_word(&mut rng, 'ゎ', "あ"), None);
+    }
+}
@@ -1,10 +1,109 @@
+use std::ops::Range;
+
 use serde::{Deserialize, Serialize};
 
-use diff;
-use split::split_romaji;
+use cost::PhoneticCost;
+use diff::{self, CostModel};
+use mistake::{find_mistakes, Mistake};
+use normalize::normalize;
+use split::{split_romaji, tokenize, try_split_romaji, ConversionError, Syllable};
+use tables::{LongVowels, RomanizationSystem, ACCENTS};
+use typo::{is_extra_key, is_typo, ErrorKind, Layout};
+
+/// Converts the kana in `input` to romaji using the given system and
+/// spelling for long vowels.
+pub fn to_romaji(input: &str, system: RomanizationSystem, long: LongVowels) -> String {
+    compose(&split_romaji(input, system, long).concat())
+}
+
+/// Same as `to_romaji`, but fails if the input has invalid kana
+/// sequences (e.g. a trailing `っ`).
+pub fn try_to_romaji(
+    input: &str,
+    system: RomanizationSystem,
+    long: LongVowels,
+) -> Result<String, ConversionError> {
+    Ok(compose(&try_split_romaji(input, system, long)?.concat()))
+}
 
-pub fn to_romaji(input: &str) -> String {
-    split_romaji(input).concat()
+// Replaces vowels followed by a combining macron or circumflex with
+// the precomposed character (e.g. `ā`).
+fn compose(text: &str) -> String {
+    let mut out = String::new();
+    for chr in text.chars() {
+        let last = out.chars().last();
+        if let Some(&(accent, _, _)) = ACCENTS.iter().find(|x| Some(x.1) == last && x.2 == chr) {
+            out.pop();
+            out.push(accent);
+        } else {
+            out.push(chr);
+        }
+    }
+    out
+}
+
+// Replaces precomposed vowels with macron or circumflex by the vowel
+// followed by the combining character. This is the inverse of
+// `compose`.
+fn decompose(text: &str) -> String {
+    let mut out = String::new();
+    for chr in text.chars() {
+        if let Some(&(_, vowel, mark)) = ACCENTS.iter().find(|x| x.0 == chr) {
+            out.push(vowel);
+            out.push(mark);
+        } else {
+            out.push(chr);
+        }
+    }
+    out
+}
+
+/// Status of a kana character in a `Match`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum KanaStatus {
+    /// The kana was read correctly.
+    Correct,
+
+    /// The kana was read as a different syllable.
+    Wrong,
+
+    /// The kana was not read at all.
+    Missing,
+
+    /// The kana was read correctly, but with extra input before or
+    /// after it.
+    Extra,
+}
+
+/// Result for a single kana character in a `Match`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct KanaMatch {
+    /// The kana character, from `Match::split`.
+    pub kana: char,
+
+    /// Whether the kana was read correctly.
+    pub status: KanaStatus,
+
+    /// The input text read for the kana, without any extra input.
+    pub text: String,
+
+    /// The expected romaji for the kana.
+    pub expected: String,
+
+    /// Extra input before the kana. This is only used for the first
+    /// kana, otherwise the extra input goes in `after`.
+    pub before: String,
+
+    /// Extra input after the kana.
+    pub after: String,
+
+    /// Range of the entries in `Match::diff` for the kana, including
+    /// the extra input.
+    pub diff: Range<usize>,
+
+    /// The kind of error for the kana, or `None` if it is correct.
+    /// This is `ErrorKind::Typo` only if all the errors are typos.
+    pub kind: Option<ErrorKind>,
 }
 
 /// Result of matching a kana and romaji string.
@@ -16,59 +115,231 @@ pub struct Match {
     /// The kana input string.
     pub kana: String,
 
-    /// The romaji input string.
+    /// The romaji input string, normalized, in lowercase and with any
+    /// long vowel marks decomposed.
     pub romaji: String,
 
-    /// The actual romaji translation for the given kana.
+    /// The actual romaji translation for the given kana, using the
+    /// canonical spelling for each syllable.
     pub actual: String,
 
-    /// Split syllables of `kana`.
+    /// Split syllables of `kana`, after normalization.
     pub split: Vec<char>,
 
+    /// For each character in `split`, the range of characters in
+    /// `kana` that generated it.
+    pub source: Vec<Range<usize>>,
+
     /// Diff between `romaji` and `kana`.
     pub diff: Vec<diff::Diff>,
 
-    /// The failed kana chars.
+    /// For each character in `split`, the result of the match.
+    pub chars: Vec<KanaMatch>,
+
+    /// The failed kana chars, which are the ones in `chars` that are
+    /// not `KanaStatus::Correct`.
     pub fails: Vec<char>,
+
+    /// For each entry in `diff`, the kind of error or `None` for a
+    /// `Diff::Same`.
+    pub kinds: Vec<Option<ErrorKind>>,
+
+    /// The failed kana chars for which all the errors are typos.
+    pub typos: Vec<char>,
+
+    /// True if `kana` and `romaji` do not match, but all the errors
+    /// are typos.
+    pub is_typo: bool,
+
+    /// For each entry in `diff`, the label explaining the mistake, if
+    /// one was found (see `Mistake`).
+    pub mistakes: Vec<Option<Mistake>>,
+
+    /// Syllables for `split`, used to classify the errors.
+    syllables: Vec<Syllable>,
 }
 
 impl Match {
+    /// Matches the kana and romaji strings, using `PhoneticCost` to
+    /// align the mistakes and the default `Layout` to find typos.
     pub fn new(kana: &str, romaji: &str) -> Match {
-        let syllables = split_romaji(kana);
-        let romaji = romaji.to_lowercase().replace("-", "ー");
-        let diff = diff::diff(&syllables, &romaji);
-        let actual = syllables.concat();
-        let is_match = diff.iter().all(|ref x| match x {
-            diff::Diff::Same(_) => true,
-            _ => false,
-        });
-        let split: Vec<_> = kana.chars().collect();
-        let mut fails = Vec::new();
-
-        let mut kana_index = 0;
-        for it in &diff {
-            match it {
-                diff::Diff::Same(_) => {
-                    kana_index += 1;
-                }
-                diff::Diff::Insert(_) | diff::Diff::Change(_, _) => {
-                    fails.push(split[kana_index]);
-                    kana_index += 1;
-                }
-                diff::Diff::Delete(_) => {}
-            }
-        }
+        Match::with_cost(kana, romaji, &PhoneticCost)
+    }
+
+    /// Same as `new`, but with the given `CostModel` for the diff.
+    pub fn with_cost<C: CostModel + ?Sized>(kana: &str, romaji: &str, model: &C) -> Match {
+        let syllables = tokenize(kana, RomanizationSystem::default(), LongVowels::default());
+        let romaji = decompose(&normalize(romaji).text.to_lowercase());
+        let diff = diff::diff_with(&syllables, &romaji, model);
+        let actual = syllables.iter().map(|x| x.romaji.as_str()).collect();
+        let is_match = diff.iter().all(|x| matches!(x, diff::Diff::Same(_)));
+        let split: Vec<_> = normalize(kana).text.chars().collect();
+        let mistakes = find_mistakes(&split, &syllables, &diff);
+        let chars = align(&split, &syllables, &diff);
+        let fails = chars
+            .iter()
+            .filter(|x| x.status != KanaStatus::Correct)
+            .map(|x| x.kana)
+            .collect();
 
-        Match {
+        let mut result = Match {
             is_match,
             kana: String::from(kana),
             romaji,
             actual,
             split,
+            source: syllables.iter().map(|x| x.range.clone()).collect(),
             diff,
+            chars,
             fails,
+            kinds: Vec::new(),
+            typos: Vec::new(),
+            is_typo: false,
+            mistakes,
+            syllables,
+        };
+        result.classify(Layout::default());
+        result
+    }
+
+    /// Classifies each error in `diff` as either a typo in the given
+    /// keyboard layout or a recognition error, and updates `kinds`,
+    /// the kind for each of the `chars`, `typos` and `is_typo`.
+    ///
+    /// Changes are typos if they are a typo for any accepted spelling
+    /// of the syllable (see `is_typo`). Extra text in the input is a
+    /// typo if it is a single key that is the same or adjacent to the
+    /// key before or after it, unless it is in `mistakes`. Missing
+    /// syllables are always recognition errors.
+    ///
+    /// ```
+    /// use kana::{ErrorKind, Layout, Match};
+    ///
+    /// let mut m = Match::new("かさ", "kqsa");
+    /// assert_eq!(m.kinds, vec![Some(ErrorKind::Typo), None]);
+    /// assert!(m.is_typo);
+    ///
+    /// m.classify(Layout::Dvorak);
+    /// assert_eq!(m.kinds, vec![Some(ErrorKind::Recognition), None]);
+    /// assert!(!m.is_typo);
+    /// ```
+    pub fn classify(&mut self, layout: Layout) {
+        let input: Vec<char> = self.romaji.chars().collect();
+
+        // Entries not in any of the `chars` (i.e. input for an empty
+        // kana) are never typos.
+        let mut kinds = vec![Some(ErrorKind::Recognition); self.diff.len()];
+        let mut input_index = 0;
+        for (index, chr) in self.chars.iter_mut().enumerate() {
+            for entry in chr.diff.clone() {
+                kinds[entry] = match &self.diff[entry] {
+                    diff::Diff::Same(text) => {
+                        input_index += text.chars().count();
+                        None
+                    }
+                    diff::Diff::Change(text, _) => {
+                        let accepted = &self.syllables[index].accepted;
+                        let typo = accepted.iter().any(|x| is_typo(layout, text, x));
+                        input_index += text.chars().count();
+                        Some(if typo { ErrorKind::Typo } else { ErrorKind::Recognition })
+                    }
+                    diff::Diff::Insert(_) => Some(ErrorKind::Recognition),
+                    diff::Diff::Delete(text) => {
+                        // Extra text with a label (e.g. the `i` in `kiya`
+                        // for `きゃ`) is never a typo.
+                        let mut chars = text.chars();
+                        let end = input_index + text.chars().count();
+                        let typo = match (chars.next(), chars.next()) {
+                            _ if self.mistakes[entry].is_some() => false,
+                            (Some(key), None) => {
+                                let mut rest = input[..input_index].to_vec();
+                                rest.extend_from_slice(&input[end..]);
+                                is_extra_key(layout, key, &rest, input_index)
+                            }
+                            _ => false,
+                        };
+                        input_index = end;
+                        Some(if typo { ErrorKind::Typo } else { ErrorKind::Recognition })
+                    }
+                };
+            }
+
+            // A kana can have more than one error (e.g. a change and the
+            // extra text after it), so it is a typo only if all of them
+            // are typos.
+            let errors = &kinds[chr.diff.clone()];
+            chr.kind = if chr.status == KanaStatus::Correct {
+                None
+            } else if errors.contains(&Some(ErrorKind::Recognition)) {
+                Some(ErrorKind::Recognition)
+            } else {
+                Some(ErrorKind::Typo)
+            };
         }
+
+        let typos = self.chars.iter().filter(|x| x.kind == Some(ErrorKind::Typo));
+        self.typos = typos.map(|x| x.kana).collect();
+        self.is_typo = !self.is_match && kinds.iter().all(|x| x != &Some(ErrorKind::Recognition));
+        self.kinds = kinds;
+    }
+}
+
+/// Aligns each character in `split` with its entries in the diff and
+/// the input text for it.
+///
+/// Extra input (i.e. a `Diff::Delete`) goes with the kana before it,
+/// except at the start, where it goes with the first kana.
+fn align(split: &[char], syllables: &[Syllable], diff: &[diff::Diff]) -> Vec<KanaMatch> {
+    let mut result: Vec<KanaMatch> = split
+        .iter()
+        .zip(syllables)
+        .map(|(&kana, syllable)| KanaMatch {
+            kana,
+            status: KanaStatus::Correct,
+            text: String::new(),
+            expected: syllable.romaji.clone(),
+            before: String::new(),
+            after: String::new(),
+            diff: 0..0,
+            kind: None,
+        })
+        .collect();
+
+    let mut kana_index = 0;
+    let mut start = 0;
+    for (index, it) in diff.iter().enumerate() {
+        let (status, text) = match it {
+            diff::Diff::Same(text) => (KanaStatus::Correct, text.as_str()),
+            diff::Diff::Change(text, _) => (KanaStatus::Wrong, text.as_str()),
+            diff::Diff::Insert(_) => (KanaStatus::Missing, ""),
+            diff::Diff::Delete(text) => {
+                if kana_index > 0 {
+                    let chr = &mut result[kana_index - 1];
+                    chr.after.push_str(text);
+                    chr.diff.end = index + 1;
+                    if chr.status == KanaStatus::Correct {
+                        chr.status = KanaStatus::Extra;
+                    }
+                    start = index + 1;
+                } else if let Some(chr) = result.first_mut() {
+                    chr.before.push_str(text);
+                }
+                continue;
+            }
+        };
+
+        let chr = &mut result[kana_index];
+        chr.status = if status == KanaStatus::Correct && !chr.before.is_empty() {
+            KanaStatus::Extra
+        } else {
+            status
+        };
+        chr.text = String::from(text);
+        chr.diff = start..index + 1;
+        kana_index += 1;
+        start = index + 1;
     }
+    result
 }
 
 // spell-checker: disable
@@ -76,6 +347,13 @@ impl Match {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use diff::LengthCost;
+
+    // Most tests were written for a single romanization, so this uses
+    // the default system and spells long vowels as doubled vowels.
+    fn to_romaji(input: &str) -> String {
+        super::to_romaji(input, RomanizationSystem::ModifiedHepburn, LongVowels::Doubled)
+    }
 
     #[test]
     fn test_is_match() {
@@ -97,6 +375,280 @@ mod tests {
         ));
     }
 
+    #[test]
+    fn test_match_normalized() {
+        let m = Match::new("ｶﾞｯｺｰ", "bakkoo");
+        assert_eq!(m.split, vec!['ガ', 'ッ', 'コ', 'ー']);
+        assert_eq!(m.source, vec![0..2, 2..3, 3..4, 4..5]);
+        assert_eq!(m.fails, vec!['ガ']);
+    }
+
+    #[test]
+    fn test_match_mistakes() {
+        use diff::Diff;
+
+        fn diff(kana: &str, romaji: &str) -> Vec<Diff> {
+            Match::new(kana, romaji).diff
+        }
+
+        fn s(txt: &str) -> Diff {
+            Diff::Same(String::from(txt))
+        }
+
+        fn d(txt: &str) -> Diff {
+            Diff::Delete(String::from(txt))
+        }
+
+        fn i(txt: &str) -> Diff {
+            Diff::Insert(String::from(txt))
+        }
+
+        fn c(a: &str, b: &str) -> Diff {
+            Diff::Change(String::from(a), String::from(b))
+        }
+
+        // Similar syllables are changed one to one.
+        assert_eq!(diff("しち", "chishi"), vec![c("chi", "shi"), c("shi", "chi")]);
+        assert_eq!(diff("ちず", "shizu"), vec![c("shi", "chi"), s("zu")]);
+        assert_eq!(diff("つき", "suki"), vec![c("su", "tsu"), s("ki")]);
+        assert_eq!(diff("ばか", "paka"), vec![c("pa", "ba"), s("ka")]);
+        assert_eq!(diff("りんご", "lingo"), vec![c("li", "ri"), s("n"), s("go")]);
+        assert_eq!(diff("ねこ", "meko"), vec![c("me", "ne"), s("ko")]);
+
+        // Missing sokuon and doubled consonants.
+        assert_eq!(diff("がっこう", "gakou"), vec![s("ga"), i("k"), s("ko"), s("u")]);
+        assert_eq!(diff("ざっし", "zashi"), vec![s("za"), i("s"), s("shi")]);
+        assert_eq!(diff("きて", "kitte"), vec![s("ki"), d("t"), s("te")]);
+
+        // Missing long vowel.
+        assert_eq!(
+            diff("ありがとう", "arigato"),
+            vec![s("a"), s("ri"), s("ga"), s("to"), i("u")]
+        );
+
+        assert_eq!(Match::new("がっこう", "gakou").fails, vec!['っ']);
+        assert_eq!(Match::new("しち", "chishi").fails, vec!['し', 'ち']);
+    }
+
+    #[test]
+    fn test_match_typos() {
+        use typo::ErrorKind::{Recognition, Typo};
+
+        let m = Match::new("かさ", "kasq");
+        assert_eq!(m.kinds, vec![None, Some(Typo)]);
+        assert_eq!(m.fails, vec!['さ']);
+        assert_eq!(m.typos, vec!['さ']);
+        assert!(m.is_typo);
+
+        let m = Match::new("すし", "suhsi");
+        assert_eq!(m.kinds, vec![None, Some(Typo)]);
+        assert!(m.is_typo);
+
+        let m = Match::new("ねこ", "nekop");
+        assert_eq!(m.kinds, vec![None, None, Some(Typo)]);
+        assert_eq!(m.fails, vec!['こ']);
+        assert_eq!(m.typos, vec!['こ']);
+        assert!(m.is_typo);
+
+        let m = Match::new("かさ", "kita");
+        assert_eq!(m.kinds, vec![Some(Recognition), Some(Recognition)]);
+        assert!(m.typos.is_empty());
+        assert!(!m.is_typo);
+
+        let m = Match::new("かさ", "kqta");
+        assert_eq!(m.kinds, vec![Some(Typo), Some(Recognition)]);
+        assert_eq!(m.typos, vec!['か']);
+        assert!(!m.is_typo);
+
+        // Readings of other kana are not typos, even with adjacent keys.
+        let m = Match::new("すし", "sushu");
+        assert_eq!(m.kinds, vec![None, Some(Recognition)]);
+        assert!(!m.is_typo);
+
+        let m = Match::new("き", "ku");
+        assert_eq!(m.kinds, vec![Some(Recognition)]);
+
+        let m = Match::new("かさ", "kasa");
+        assert_eq!(m.kinds, vec![None, None]);
+        assert!(!m.is_typo);
+    }
+
+    #[test]
+    fn test_match_with_cost() {
+        // The extra `chi` at the start goes with the first kana.
+        let m = Match::with_cost("しち", "chishi", &LengthCost);
+        assert_eq!(m.fails, vec!['し', 'ち']);
+        assert_eq!(m.chars[0].before, "chi");
+        assert_eq!(m.chars[0].status, KanaStatus::Extra);
+        assert_eq!(m.chars[1].status, KanaStatus::Missing);
+        assert!(!m.is_match);
+    }
+
+    #[test]
+    fn test_match_chars() {
+        fn chars(kana: &str, romaji: &str) -> Vec<(KanaStatus, String, String)> {
+            let m = Match::new(kana, romaji);
+            let chars = m.chars.into_iter();
+            chars.map(|x| (x.status, x.text, x.after)).collect()
+        }
+
+        fn c(status: KanaStatus, text: &str, after: &str) -> (KanaStatus, String, String) {
+            (status, String::from(text), String::from(after))
+        }
+
+        use self::KanaStatus::*;
+
+        assert_eq!(chars("", ""), vec![]);
+        assert_eq!(chars("ねこ", "neko"), vec![c(Correct, "ne", ""), c(Correct, "ko", "")]);
+        assert_eq!(chars("ねこ", "nekop"), vec![c(Correct, "ne", ""), c(Extra, "ko", "p")]);
+        assert_eq!(chars("かさ", "kasi"), vec![c(Correct, "ka", ""), c(Wrong, "si", "")]);
+        assert_eq!(
+            chars("きって", "kite"),
+            vec![c(Correct, "ki", ""), c(Missing, "", ""), c(Correct, "te", "")]
+        );
+        assert_eq!(
+            chars("きゃ", "kiya"),
+            vec![c(Extra, "k", "i"), c(Correct, "ya", "")]
+        );
+        assert_eq!(Match::new("きゃ", "kiya").chars[0].kind, Some(ErrorKind::Recognition));
+
+        // Repeated kana are told apart.
+        let m = Match::new("ははは", "hahapa");
+        assert_eq!(m.fails, vec!['は']);
+        assert_eq!(m.chars[2].status, Wrong);
+        assert_eq!(m.chars[2].expected, "ha");
+        assert_eq!(m.chars[2].diff, 2..3);
+        assert_eq!(m.chars[2].kind, Some(ErrorKind::Recognition));
+
+        // Each entry in the diff belongs to a single kana.
+        let m = Match::new("がっこう", "gaakkoi");
+        let ranges: Vec<_> = m.chars.iter().map(|x| x.diff.clone()).collect();
+        assert_eq!(ranges, vec![0..2, 2..3, 3..4, 4..5]);
+        assert_eq!(m.diff.len(), 5);
+    }
+
+    #[test]
+    fn test_is_match_accepted() {
+        fn is_match(kana: &str, romaji: &str) -> bool {
+            Match::new(kana, romaji).is_match
+        }
+
+        assert!(is_match("つなみ", "tsunami"));
+        assert!(is_match("つなみ", "tunami"));
+        assert!(is_match("すし", "susi"));
+        assert!(is_match("ふじ", "huzi"));
+        assert!(is_match("はなぢ", "hanaji"));
+        assert!(is_match("はなぢ", "hanadi"));
+        assert!(is_match("はなぢ", "hanazi"));
+        assert!(is_match("つづく", "tsuzuku"));
+        assert!(is_match("つづく", "tuduku"));
+        assert!(is_match("ぢ", "dji"));
+        assert!(is_match("はなぢ", "hanadji"));
+        assert!(is_match("ぢゃ", "dja"));
+        assert!(is_match("つづく", "tsudzuku"));
+        assert!(is_match("ほんを", "honwo"));
+        assert!(is_match("しゃしん", "syasin"));
+        assert!(is_match("こんにちは", "konnnitiha"));
+        assert!(is_match("パーティー", "pa-ti-"));
+        assert!(!is_match("しゃ", "sa"));
+        assert!(!is_match("ちゃ", "ta"));
+        assert!(!is_match("し", "su"));
+        assert!(is_match("ゐる", "iru"));
+        assert!(is_match("ゐる", "wiru"));
+        assert!(is_match("くゎし", "kwashi"));
+        assert!(is_match("いすゞ", "isuzu"));
+        assert!(is_match("ｶﾀｶﾅ", "katakana"));
+        assert!(is_match("ｶﾞｯｺｰ", "gakkou"));
+        assert!(is_match("か\u{3099}っこう", "gakkou"));
+        assert!(is_match("かな", "ｋａｎａ"));
+        assert!(is_match("カナ", "ＫＡＮＡ"));
+        assert!(!is_match("いすゞ", "isusu"));
+        assert_eq!(Match::new("こゝろ", "kogoro").fails, vec!['ゝ']);
+        assert!(is_match("きゃゝ", "kyakya"));
+        assert!(is_match("しゃゝ", "syasya"));
+        assert!(!is_match("きゃゝ", "kyaki"));
+
+        assert!(is_match("きんえん", "kin'en"));
+        assert!(is_match("きんえん", "kinnen"));
+        assert!(is_match("きんえん", "kin-en"));
+        assert!(!is_match("きんえん", "kinen"));
+        assert!(is_match("こんや", "kon'ya"));
+        assert!(!is_match("こんや", "konya"));
+        assert!(is_match("ほんね", "honne"));
+        assert!(is_match("ほんね", "hon'ne"));
+        assert!(is_match("さんま", "san'ma"));
+        assert!(is_match("ほん", "hon'"));
+
+        let m = Match::new("きんえん", "kinen");
+        assert_eq!(m.fails, vec!['ん']);
+
+        let m = Match::new("ちず", "tizu");
+        assert_eq!(m.actual, "chizu");
+        assert!(m.fails.is_empty());
+    }
+
+    #[test]
+    fn test_is_match_long_vowels() {
+        fn is_match(kana: &str, romaji: &str) -> bool {
+            Match::new(kana, romaji).is_match
+        }
+
+        for romaji in &["toukyou", "tōkyō", "tôkyô", "tookyoo", "TŌKYŌ", "tōkyou"] {
+            assert!(is_match("とうきょう", romaji), "{}", romaji);
+        }
+        for romaji in &["koohii", "kōhī", "kôhî", "kouhii", "ko-hi-"] {
+            assert!(is_match("コーヒー", romaji), "{}", romaji);
+        }
+        for romaji in &["sensei", "sensē", "sensê"] {
+            assert!(is_match("せんせい", romaji), "{}", romaji);
+        }
+        for romaji in &["okaasan", "okāsan", "okâsan"] {
+            assert!(is_match("おかあさん", romaji), "{}", romaji);
+        }
+        assert!(!is_match("こ", "kō"));
+
+        // A vowel across a morpheme boundary is accepted either way.
+        assert!(is_match("おもう", "omou"));
+        assert!(is_match("おもう", "omō"));
+        assert!(!is_match("とうきょう", "tokyo"));
+    }
+
+    #[test]
+    fn test_to_romaji_long_vowels() {
+        fn romaji(input: &str, long: LongVowels) -> String {
+            super::to_romaji(input, RomanizationSystem::ModifiedHepburn, long)
+        }
+
+        let text = "とうきょう パーティー せんせい おおきい おねえさん";
+        assert_eq!(
+            romaji(text, LongVowels::Doubled),
+            "toukyou paatii sensei ookii oneesan"
+        );
+        assert_eq!(
+            romaji(text, LongVowels::Macron),
+            "tōkyō pātī sensei ōkii onēsan"
+        );
+        assert_eq!(
+            romaji(text, LongVowels::Circumflex),
+            "tôkyô pâtî sensei ôkii onêsan"
+        );
+        assert_eq!(romaji("コーヒー とおり", LongVowels::Ou), "kouhii touri");
+        assert_eq!(romaji("コーヒー とうきょう", LongVowels::Oo), "koohii tookyoo");
+    }
+
+    #[test]
+    fn test_try_to_romaji() {
+        let system = RomanizationSystem::ModifiedHepburn;
+        assert_eq!(
+            try_to_romaji("とうきょう", system, LongVowels::Macron),
+            Ok(String::from("tōkyō"))
+        );
+
+        let err = try_to_romaji("あっ", system, LongVowels::Doubled).unwrap_err();
+        assert_eq!(err.position, 1..2);
+        assert_eq!(err.to_string(), "small tsu is not followed by a kana at position 1");
+    }
+
     #[test]
     fn test_to_romaji_non_kana() {
         assert_eq!(to_romaji(""), "");
@@ -112,7 +664,7 @@ mod tests {
         assert_eq!(to_romaji("さしすせそ"), "sashisuseso");
         assert_eq!(to_romaji("ざじずぜぞ"), "zajizuzezo");
         assert_eq!(to_romaji("たちつてと"), "tachitsuteto");
-        assert_eq!(to_romaji("だぢづでど"), "dadjidzudedo");
+        assert_eq!(to_romaji("だぢづでど"), "dajizudedo");
         assert_eq!(to_romaji("なにぬねの"), "naninuneno");
         assert_eq!(to_romaji("はひふへほ"), "hahifuheho");
         assert_eq!(to_romaji("ばびぶべぼ"), "babibubebo");
@@ -120,7 +672,7 @@ mod tests {
         assert_eq!(to_romaji("まみむめも"), "mamimumemo");
         assert_eq!(to_romaji("やゆよ"), "yayuyo");
         assert_eq!(to_romaji("らりるれろ"), "rarirurero");
-        assert_eq!(to_romaji("わを"), "wawo");
+        assert_eq!(to_romaji("わを"), "wao");
         assert_eq!(to_romaji("ん"), "n");
     }
 
@@ -132,7 +684,7 @@ mod tests {
         assert_eq!(to_romaji("サシスセソ"), "sashisuseso");
         assert_eq!(to_romaji("ザジズゼゾ"), "zajizuzezo");
         assert_eq!(to_romaji("タチツテト"), "tachitsuteto");
-        assert_eq!(to_romaji("ダヂヅデド"), "dadjidzudedo");
+        assert_eq!(to_romaji("ダヂヅデド"), "dajizudedo");
         assert_eq!(to_romaji("ナニヌネノ"), "naninuneno");
         assert_eq!(to_romaji("ハヒフヘホ"), "hahifuheho");
         assert_eq!(to_romaji("バビブベボ"), "babibubebo");
@@ -140,10 +692,45 @@ mod tests {
         assert_eq!(to_romaji("マミムメモ"), "mamimumemo");
         assert_eq!(to_romaji("ヤユヨ"), "yayuyo");
         assert_eq!(to_romaji("ラリルレロ"), "rarirurero");
-        assert_eq!(to_romaji("ワヲ"), "wawo");
+        assert_eq!(to_romaji("ワヲ"), "wao");
         assert_eq!(to_romaji("ン"), "n");
     }
 
+    #[test]
+    fn test_to_romaji_systems() {
+        fn check(system: RomanizationSystem, input: &str, expected: &str) {
+            assert_eq!(super::to_romaji(input, system, LongVowels::Doubled), expected, "{:?}", system);
+        }
+
+        use tables::RomanizationSystem::*;
+
+        let text = "しちつふじぢづを しゃちゃじゃぢゃ";
+        check(ModifiedHepburn, text, "shichitsufujijizuo shachajaja");
+        check(TraditionalHepburn, text, "shichitsufujijizuwo shachajaja");
+        check(KunreiShiki, text, "sitituhuzizizuo syatyazyazya");
+        check(NihonShiki, text, "sitituhuzididuwo syatyazyadya");
+        check(Wapuro, text, "shichitsufujididuwo shachajadya");
+
+        let text = "しんぶん さんま こんにちは";
+        check(ModifiedHepburn, text, "shinbun sanma konnichiha");
+        check(TraditionalHepburn, text, "shimbun samma konnichiha");
+        check(KunreiShiki, text, "sinbun sanma konnitiha");
+        check(Wapuro, text, "shinnbunn sannma konnnichiha");
+
+        let text = "こっち シェフ パーティー";
+        check(ModifiedHepburn, text, "kotchi shefu paatii");
+        check(KunreiShiki, text, "kotti syehu paatii");
+        check(Wapuro, text, "kotchi shefu pa-ti-");
+    }
+
+    #[test]
+    fn test_to_romaji_syllabic_n() {
+        assert_eq!(to_romaji("きんえん きねん"), "kin'en kinen");
+        assert_eq!(to_romaji("こんや こにゃ"), "kon'ya konya");
+        assert_eq!(to_romaji("ホンヤク"), "hon'yaku");
+        assert_eq!(to_romaji("しんぶん"), "shinbun");
+    }
+
     #[test]
     fn test_to_romaji_small_u() {
         assert_eq!(to_romaji("まって"), "matte");
@@ -154,14 +741,14 @@ mod tests {
         assert_eq!(to_romaji("アッサリ"), "assari");
         assert_eq!(to_romaji("アッイェ"), "ayye");
 
-        assert_eq!(to_romaji("あっ"), "a~tsu");
-        assert_eq!(to_romaji("いっ"), "i~tsu");
-        assert_eq!(to_romaji("うっ"), "u~tsu");
-        assert_eq!(to_romaji("えっ"), "e~tsu");
-        assert_eq!(to_romaji("おっ"), "o~tsu");
+        assert_eq!(to_romaji("あっ"), "axtsu");
+        assert_eq!(to_romaji("いっ"), "ixtsu");
+        assert_eq!(to_romaji("うっ"), "uxtsu");
+        assert_eq!(to_romaji("えっ"), "extsu");
+        assert_eq!(to_romaji("おっ"), "oxtsu");
         assert_eq!(to_romaji("っっコ"), "kkko");
-        assert_eq!(to_romaji("っ"), "~tsu");
-        assert_eq!(to_romaji("ッ"), "~tsu");
+        assert_eq!(to_romaji("っ"), "xtsu");
+        assert_eq!(to_romaji("ッ"), "xtsu");
     }
 
     #[test]
@@ -217,6 +804,35 @@ mod tests {
         assert_eq!(to_romaji("ヴァヴィヴヴェヴォ"), "vavivuvevo");
     }
 
+    #[test]
+    fn test_to_romaji_hiragana_digraph() {
+        assert_eq!(to_romaji("しゃししゅしぇしょ"), "shashishushesho");
+        assert_eq!(to_romaji("ちゃちちゅちぇちょ"), "chachichuchecho");
+        assert_eq!(to_romaji("てぃとぅでぃどぅでゅ"), "titudidudyu");
+        assert_eq!(to_romaji("ふぁふぃふふぇふぉ"), "fafifufefo");
+        assert_eq!(to_romaji("わうぃううぇうぉ"), "wawiuwewo");
+        assert_eq!(to_romaji("いぇくぁぐぁ"), "yekwagwa");
+        assert_eq!(to_romaji("ゔぁゔぃゔゔぇゔぉ"), "vavivuvevo");
+        assert_eq!(to_romaji("とうぃ"), "towi");
+        assert_eq!(to_romaji("きゃゝ"), "kyakya");
+        assert_eq!(to_romaji("ぎょゞ ちゅゝ"), "gyogyo chuchu");
+    }
+
+    #[test]
+    fn test_to_romaji_historical_and_small() {
+        assert_eq!(to_romaji("ゐゑ ヰヱ"), "ie ie");
+        assert_eq!(
+            super::to_romaji("ゐゑ ヰヱ", RomanizationSystem::NihonShiki, LongVowels::Doubled),
+            "wiwe wiwe"
+        );
+        assert_eq!(to_romaji("ヰスキー"), "isukii");
+        assert_eq!(to_romaji("ヷヸヴヹヺ"), "vavivuvevo");
+        assert_eq!(to_romaji("ヵヶゕゖ"), "kakekake");
+        assert_eq!(to_romaji("クヮ グヮ くゎ ぐゎ"), "kwa gwa kwa gwa");
+        assert_eq!(to_romaji("ゎ"), "xwa");
+        assert_eq!(to_romaji("ヮ"), "xwa");
+    }
+
     #[test]
     fn test_to_romaji_long() {
         assert_eq!(
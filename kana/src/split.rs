@@ -1,127 +1,541 @@
+use std::error;
+use std::fmt;
+use std::ops::Range;
+
+use serde::{Deserialize, Serialize};
+
+use diff;
+use normalize::normalize;
 use tables::*;
 
-/// Representation for invalid `SmallTsu` characters.
-const TSU: &'static str = "~tsu";
+/// Kind of a `Syllable` returned by `tokenize`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SyllableKind {
+    /// A kana with its own syllable (e.g. `か`).
+    Plain,
+
+    /// Either part of a digraph. The first kana is spelled with the
+    /// digraph prefix and the small kana with the suffix (e.g. `k` and
+    /// `ya` for `きゃ`).
+    Digraph,
+
+    /// A `っ` or `ッ`, spelled by doubling the next consonant.
+    Sokuon,
+
+    /// A long vowel, either from the `ー` or from a hiragana vowel
+    /// extending the previous syllable (e.g. `おう`).
+    LongVowel,
+
+    /// Any character that is not kana, which is kept as is.
+    NonKana,
+
+    /// Invalid kana sequence. The romaji is how the character would
+    /// be typed in an IME (e.g. `xtsu`).
+    Error(SyllableError),
+}
+
+/// Error for a `SyllableKind::Error` syllable.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SyllableError {
+    /// A small kana that is not part of a digraph (e.g. `ゃ` at the
+    /// start of the text).
+    SmallKana,
+
+    /// A `っ` or `ッ` that is not followed by another kana (e.g. a
+    /// trailing `っ`).
+    Sokuon,
+
+    /// An iteration mark without a previous kana to repeat.
+    IterationMark,
+
+    /// Romaji that does not form a syllable when converting to kana
+    /// (e.g. the `q` in `qa`).
+    Romaji,
+}
+
+impl fmt::Display for SyllableError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let text = match self {
+            SyllableError::SmallKana => "small kana is not part of a digraph",
+            SyllableError::Sokuon => "small tsu is not followed by a kana",
+            SyllableError::IterationMark => "iteration mark has no kana to repeat",
+            SyllableError::Romaji => "romaji does not form a syllable",
+        };
+        f.write_str(text)
+    }
+}
+
+/// Error returned by the fallible conversion functions (e.g.
+/// `try_split_romaji`) for the first invalid syllable in the text.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ConversionError {
+    /// Range of characters in the source text for the invalid
+    /// syllable.
+    pub position: Range<usize>,
+
+    /// The reason for the error.
+    pub reason: SyllableError,
+}
+
+impl fmt::Display for ConversionError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} at position {}", self.reason, self.position.start)
+    }
+}
+
+impl error::Error for ConversionError {}
+
+/// A romaji syllable in the result of `tokenize`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Syllable {
+    /// Range of characters in the source text for this syllable.
+    ///
+    /// This is usually a single character, except when normalization
+    /// merges characters (e.g. `ｶﾞ`).
+    pub range: Range<usize>,
+
+    /// The romaji for the syllable in the requested system.
+    pub romaji: String,
+
+    /// All accepted spellings for the syllable, including `romaji`.
+    ///
+    /// Those are the spellings for the same syllable in any of the
+    /// romanization systems.
+    pub accepted: Vec<String>,
+
+    /// The kind of syllable.
+    pub kind: SyllableKind,
+}
+
+impl diff::Spelling for Syllable {
+    fn text(&self) -> &str {
+        &self.romaji
+    }
+
+    fn spellings(&self) -> Vec<&str> {
+        self.accepted.iter().map(|x| x.as_str()).collect()
+    }
+}
+
+/// Split the kana text into romaji syllables using the given
+/// romanization system.
+///
+/// This is the same as `tokenize`, but returns only the romaji.
+pub fn split_romaji(text: &str, system: RomanizationSystem, long: LongVowels) -> Vec<String> {
+    tokenize(text, system, long)
+        .into_iter()
+        .map(|x| x.romaji)
+        .collect()
+}
+
+/// Same as `split_romaji`, but fails on the first invalid syllable
+/// instead of spelling it as typed in an IME.
+pub fn try_split_romaji(
+    text: &str,
+    system: RomanizationSystem,
+    long: LongVowels,
+) -> Result<Vec<String>, ConversionError> {
+    let mut out = Vec::new();
+    for syllable in tokenize(text, system, long) {
+        if let SyllableKind::Error(reason) = syllable.kind {
+            return Err(ConversionError {
+                position: syllable.range,
+                reason,
+            });
+        }
+        out.push(syllable.romaji);
+    }
+    Ok(out)
+}
+
+/// Split the kana text into romaji syllables using the given
+/// romanization system.
+///
+/// The text is first normalized with `normalize`, and this returns
+/// exactly one syllable per character in the normalized text, which
+/// means that digraphs and special characters cause what would be
+/// considered a syllable to be split.
+///
+/// Each syllable also includes the spellings from the other systems,
+/// which are aligned so that mixing syllables from different systems
+/// never changes the reading (e.g. `しゃ` is either `sh-a` or `sy-a`,
+/// but never `s-a`).
+pub fn tokenize(text: &str, system: RomanizationSystem, long: LongVowels) -> Vec<Syllable> {
+    let normalized = normalize(text);
+    let text = &normalized.text;
+    let canonical = split_system(text, system, long, false);
+    let mut out: Vec<Syllable> = canonical
+        .iter()
+        .zip(normalized.source)
+        .map(|(x, range)| Syllable {
+            range,
+            romaji: x.0.clone(),
+            accepted: vec![x.0.clone()],
+            kind: x.1,
+        })
+        .collect();
+    let canonical: Vec<String> = canonical.into_iter().map(|x| x.0).collect();
+
+    // Positions that are followed by the second half of a digraph.
+    let chars: Vec<char> = text.chars().collect();
+    let is_digraph = |index: usize| -> bool {
+        let next = chars.get(index + 1).and_then(|&x| Kana::get(x));
+        matches!(next, Some(Kana::Small(_, _)))
+    };
+
+    let mut add_accepted = |split: Vec<(String, SyllableKind)>| {
+        let mut split: Vec<String> = split.into_iter().map(|x| x.0).collect();
+        for i in 0..split.len() {
+            // Align digraphs with the suffix of the canonical
+            // spelling, when possible.
+            if is_digraph(i) && split[i + 1] != canonical[i + 1] {
+                let whole = format!("{}{}", split[i], split[i + 1]);
+                if whole.ends_with(&canonical[i + 1]) {
+                    let prefix = &whole[..whole.len() - canonical[i + 1].len()];
+                    split[i] = String::from(prefix);
+                    split[i + 1] = canonical[i + 1].clone();
+                }
+            }
+
+            let accepted = &mut out[i].accepted;
+            if !accepted.contains(&split[i]) {
+                accepted.push(split[i].clone());
+            }
+        }
+    };
+
+    for &other in RomanizationSystem::ALL {
+        if other != system {
+            add_accepted(split_system(text, other, long, false));
+        }
+    }
+
+    // For long vowels we also accept `ii` and `ei` with macrons or
+    // circumflexes.
+    for &other in LongVowels::ALL {
+        add_accepted(split_system(text, system, other, true));
+    }
+
+    // The `dji` and `dzu` spellings for `ぢ` and `づ` were the default
+    // before the romanization systems, so they are still accepted.
+    for (i, syllable) in out.iter_mut().enumerate() {
+        let spelling = match (chars[i], is_digraph(i)) {
+            ('ぢ', false) | ('ヂ', false) => "dji",
+            ('ぢ', true) | ('ヂ', true) => "dj",
+            ('づ', false) | ('ヅ', false) => "dzu",
+            ('づ', true) | ('ヅ', true) => "dz",
+            _ => continue,
+        };
+        if !syllable.accepted.iter().any(|x| x == spelling) {
+            syllable.accepted.push(String::from(spelling));
+        }
+    }
+
+    // A syllabic `ん` can always be typed as `n'` or `nn`. A plain `n`
+    // is only accepted where it is unambiguous.
+    for (i, syllable) in out.iter_mut().enumerate() {
+        if chars[i] == 'ん' || chars[i] == 'ン' {
+            for &spelling in &["n'", "nn"] {
+                if !syllable.accepted.iter().any(|x| x == spelling) {
+                    syllable.accepted.push(String::from(spelling));
+                }
+            }
+        }
+    }
+
+    out
+}
+
+// Spelling for a long vowel extending `vowel`, where `written` is the
+// vowel as written in the kana. If `keep` is true, macrons and
+// circumflexes are not used.
+fn long_vowel(long: LongVowels, vowel: char, written: char, keep: bool) -> String {
+    match long {
+        LongVowels::Macron if !keep => MACRON.to_string(),
+        LongVowels::Circumflex if !keep => CIRCUMFLEX.to_string(),
+        LongVowels::Ou if vowel == 'o' => String::from("u"),
+        LongVowels::Oo if vowel == 'o' => String::from("o"),
+        _ => written.to_string(),
+    }
+}
 
-/// Invalid syllable. Must have two characters because of the digraph
-/// logic.
-const INVALID: &'static str = "~~";
+fn is_vowel(chr: char) -> bool {
+    matches!(chr, 'a' | 'i' | 'u' | 'e' | 'o')
+}
 
-// Split the kana text into romaji.
+// Split the kana text into romaji for a single system.
 //
-// This returns exactly one syllable per character in the original
-// text, which means that digraphs and special characters cause
-// what would be considered a syllable to be split.
-pub fn split_romaji(text: &str) -> Vec<(String)> {
-    let mut out: Vec<String> = Vec::new();
-    let mut chars = text.chars();
+// If `loose` is true, `いい` and `えい` are also considered long
+// vowels.
+fn split_system(
+    text: &str,
+    system: RomanizationSystem,
+    long: LongVowels,
+    loose: bool,
+) -> Vec<(String, SyllableKind)> {
+    let mut out: Vec<(String, SyllableKind)> = Vec::new();
+    let mut chars = text.chars().peekable();
     let mut tsu = (0, 0);
 
+    // Consonant used to double a syllable after a `ッ` or `っ`.
+    fn double_consonant(syllable: &str) -> char {
+        if syllable.starts_with("ch") {
+            't' // exceptionally, `chi` is doubled as `t-chi`
+        } else {
+            syllable.chars().next().unwrap_or(' ')
+        }
+    }
+
     // This appends `value` to `vec` while also taking care of
     // duplicating consonants after a `ッ` or `っ`.
-    fn push_to(vec: &mut Vec<String>, value: String, tsu: (usize, usize)) -> (usize, usize) {
+    fn push_to(
+        vec: &mut Vec<(String, SyllableKind)>,
+        value: String,
+        kind: SyllableKind,
+        tsu: (usize, usize),
+    ) -> (usize, usize) {
         let mut repeat = tsu.1;
         if repeat > 0 {
-            let chr = if value == "chi" {
-                't' // exceptionally, `chi` is doubled as `t-chi`
-            } else {
-                value.chars().next().unwrap()
-            };
+            let chr = double_consonant(&value);
             while repeat > 0 {
-                vec.push(chr.to_string());
+                vec.push((chr.to_string(), SyllableKind::Sokuon));
                 repeat -= 1;
             }
         }
-        vec.push(value);
+        vec.push((value, kind));
         (tsu.1, 0)
     }
 
-    let mut last_prefix: &'static str = INVALID;
+    let mut last_prefix: Option<&'static str> = None;
+    // The last full syllable, including the small kana of a digraph.
+    let mut last_syllable: Vec<char> = Vec::new();
     while let Some(chr) = chars.next() {
-        if let Some(chr) = Kana::get(chr) {
-            match chr {
-                // For normal characters we just add the romaji syllable.
-                Kana::Chr(_, romaji) => {
-                    tsu = push_to(&mut out, romaji.to_string(), tsu);
-                    last_prefix = romaji;
+        // An iteration mark repeats the previous syllable, so we handle
+        // it as if the syllable itself was repeated (e.g. `こゝろ` is
+        // `こころ` and `いすゞ` is `いすず`).
+        let chr = match (Kana::get(chr), last_syllable.first()) {
+            (Some(Kana::Iter(_, voice)), Some(&last)) => {
+                let last = if voice { voiced(last) } else { unvoiced(last) };
+
+                // A repeated digraph is spelled as a single syllable
+                // (e.g. `きゃゝ` is `kyakya`).
+                if last_syllable.len() > 1 {
+                    last_syllable[0] = last;
+                    let text: String = last_syllable.iter().collect();
+                    let romaji = split_system(&text, system, long, loose);
+                    let romaji = romaji.into_iter().map(|x| x.0).collect();
+                    tsu = push_to(&mut out, romaji, SyllableKind::Plain, tsu);
+                    last_prefix = None;
+                    continue;
                 }
+                last
+            }
+            _ => chr,
+        };
 
+        if let Some(kana) = Kana::get(chr) {
+            match kana {
+                // For normal characters we just add the romaji syllable.
+                //
                 // `Dig` is like a normal character, but changes when
                 // used in a digraph.
-                Kana::Dig(_, romaji, prefix) => {
-                    tsu = push_to(&mut out, romaji.to_string(), tsu);
-                    last_prefix = prefix.as_str();
+                Kana::Chr(chr, romaji) | Kana::Dig(chr, romaji, _) => {
+                    last_syllable = vec![chr];
+                    let digraph = match kana {
+                        Kana::Dig(_, _, prefix) => Some(prefix.as_str()),
+                        _ => None,
+                    };
+
+                    // A hiragana vowel extending the previous syllable
+                    // is a long vowel (e.g. `おう` and `ああ`).
+                    let written = romaji.chars().next().unwrap();
+                    let vowel = out.last().and_then(|x| x.0.chars().last());
+                    let is_long = match vowel {
+                        _ if system == RomanizationSystem::Wapuro || tsu.1 > 0 => false,
+                        _ if romaji.len() > 1 || !('ぁ'..='ゖ').contains(&chr) => false,
+                        Some(v) if is_vowel(v) => {
+                            v == written
+                                || (v == 'o' && written == 'u')
+                                || (v == 'e' && written == 'i')
+                        }
+                        _ => false,
+                    };
+                    if is_long {
+                        let keep = written == 'i' && !loose;
+                        let syllable = long_vowel(long, vowel.unwrap(), written, keep);
+                        tsu = push_to(&mut out, syllable, SyllableKind::LongVowel, tsu);
+                        last_prefix = Some(digraph.unwrap_or(romaji));
+                        continue;
+                    }
+
+                    let mut romaji = system.syllable(romaji);
+
+                    // Traditional Hepburn uses `m` for `ん` before a
+                    // labial consonant.
+                    if romaji == "n" && system == RomanizationSystem::TraditionalHepburn {
+                        if let Some(Kana::Chr(_, next)) = chars.peek().and_then(|&x| Kana::get(x)) {
+                            if next.starts_with(&['b', 'm', 'p'][..]) {
+                                romaji = "m";
+                            }
+                        }
+                    }
+
+                    // A syllabic `ん` before a vowel or `y` is marked
+                    // so that it is not read as part of the next syllable
+                    // (e.g. `きんえん` is `kin'en`, but `きねん` is `kinen`).
+                    if romaji == "n" {
+                        let next = match chars.peek().and_then(|&x| Kana::get(x)) {
+                            Some(Kana::Chr(_, next)) | Some(Kana::Dig(_, next, _)) => {
+                                system.syllable(next)
+                            }
+                            _ => "",
+                        };
+                        if next.starts_with(|c| is_vowel(c) || c == 'y') {
+                            romaji = if system == RomanizationSystem::TraditionalHepburn {
+                                "n-"
+                            } else {
+                                "n'"
+                            };
+                        }
+                    }
+
+                    tsu = push_to(&mut out, romaji.to_string(), SyllableKind::Plain, tsu);
+
+                    // A `ん` never forms a digraph, even when spelled
+                    // with more than one letter (e.g. `nn` in wāpuro).
+                    last_prefix = if chr == 'ん' || chr == 'ン' {
+                        None
+                    } else {
+                        Some(digraph.unwrap_or(romaji))
+                    };
                 }
 
                 // A `ッ` or `っ` causes the next consonant to be
                 // doubled. We implement this by duplicating.
                 Kana::SmallTsu(_) => {
                     tsu = (tsu.0, tsu.1 + 1);
-                    last_prefix = INVALID;
+                    last_prefix = None;
                 }
 
-                // A long bar `ー` causes the previous vowel to be
-                // doubled.
+                // A long bar `ー` extends the previous vowel. Wāpuro
+                // just uses the `-` as typed.
                 Kana::Bar(chr) => {
                     let last = {
-                        if let Some(last) = out.last() {
-                            last.chars().last().unwrap_or(chr).to_string()
+                        if system == RomanizationSystem::Wapuro {
+                            String::from("-")
+                        } else if let Some(last) = out.last() {
+                            let last = last.0.chars().last().unwrap_or(chr);
+                            if is_vowel(last) {
+                                long_vowel(long, last, last, false)
+                            } else {
+                                last.to_string()
+                            }
                         } else {
                             chr.to_string()
                         }
                     };
-                    tsu = push_to(&mut out, last, tsu);
+                    tsu = push_to(&mut out, last, SyllableKind::LongVowel, tsu);
+                    last_prefix = None;
+                }
+
+                // An iteration mark without a previous kana to repeat.
+                Kana::Iter(chr, _) => {
+                    let kind = SyllableKind::Error(SyllableError::IterationMark);
+                    tsu = push_to(&mut out, chr.to_string(), kind, tsu);
+                    last_prefix = None;
                 }
 
                 Kana::Small(_, suffix) => {
                     let cur_len = { out.len() };
-                    if cur_len == 0 {
-                        tsu = push_to(&mut out, suffix.as_str().to_string(), tsu);
-                    } else {
+                    // A digraph needs a previous kana with a consonant
+                    // (e.g. `アャ` is not a digraph).
+                    if let Some(last_prefix) = last_prefix.take().filter(|x| x.len() > 1) {
                         // The suffix for this digraph.
-                        let mut suffix = suffix.as_str();
+                        let mut suffix = String::from(suffix.as_str());
 
                         // The prefix is the last syllable minus the vogal.
                         let prefix = &last_prefix[..last_prefix.len() - 1];
 
                         // `y-` suffixes drop the `y` with `chi`, `shi`, `ji`
                         // and `y` (e.g. `イョ`)
-                        if suffix.starts_with("y")
-                            && (prefix.ends_with("ch")
-                                || prefix.ends_with("sh")
-                                || prefix.ends_with("j")
-                                || prefix.ends_with("y"))
+                        let is_palatal = prefix.ends_with("ch")
+                            || prefix.ends_with("sh")
+                            || prefix.ends_with('j')
+                            || prefix.ends_with('y');
+                        if suffix.starts_with('y') && is_palatal {
+                            suffix.remove(0);
+                        }
+
+                        // Same for a small `wa` after a `w-` prefix
+                        // (e.g. `クヮ`).
+                        if suffix.starts_with('w') && prefix.ends_with('w') {
+                            suffix.remove(0);
+                        }
+
+                        // Other vowels after an `i` syllable become
+                        // a `y-` suffix (e.g. `キェ` and `シェ` in
+                        // Kunrei-shiki).
+                        if suffix.len() == 1
+                            && suffix != "i"
+                            && last_prefix.ends_with('i')
+                            && !is_palatal
                         {
-                            suffix = &suffix[1..];
+                            suffix.insert(0, 'y');
                         }
 
                         // Change the full syllable to the digraph prefix.
-                        out[cur_len - 1] = String::from(prefix);
+                        out[cur_len - 1] = (String::from(prefix), SyllableKind::Digraph);
 
                         // Change any syllables added by a small TSU:
                         if tsu.0 > 0 {
-                            let new_consonant = prefix.chars().next().unwrap();
+                            let new_consonant = double_consonant(prefix);
                             while tsu.0 > 0 {
-                                out[cur_len - 1 - tsu.0] = new_consonant.to_string();
+                                out[cur_len - 1 - tsu.0].0 = new_consonant.to_string();
                                 tsu.0 -= 1;
                             }
                         }
 
                         // Append the suffix.
-                        out.push(suffix.to_string());
-                        last_prefix = INVALID;
+                        out.push((suffix, SyllableKind::Digraph));
+                        last_syllable.push(kana.get_char());
+                    } else {
+                        // A small kana that is not part of a digraph
+                        // is spelled as typed in an IME (e.g. `xya`).
+                        let kind = SyllableKind::Error(SyllableError::SmallKana);
+                        let romaji = format!("x{}", suffix.as_str());
+                        tsu = push_to(&mut out, romaji, kind, tsu);
                     }
                 }
             }
         } else {
-            tsu = push_to(&mut out, chr.to_string(), tsu);
+            tsu = push_to(&mut out, chr.to_string(), SyllableKind::NonKana, tsu);
+            last_prefix = None;
+            last_syllable.clear();
         }
     }
 
     let mut tsu = tsu.1;
     while tsu > 0 {
         tsu -= 1;
-        out.push(String::from(TSU));
+        out.push((String::new(), SyllableKind::Sokuon));
+    }
+
+    // A `ッ` or `っ` is only valid before another kana. Otherwise it is
+    // spelled as typed in an IME.
+    for i in 0..out.len() {
+        if out[i].1 != SyllableKind::Sokuon {
+            continue;
+        }
+        let next = out[i..].iter().find(|x| x.1 != SyllableKind::Sokuon);
+        let is_valid = next.is_some_and(|x| {
+            x.1 != SyllableKind::NonKana && !matches!(x.1, SyllableKind::Error(_))
+        });
+        if !is_valid {
+            out[i] = (
+                String::from("xtsu"),
+                SyllableKind::Error(SyllableError::Sokuon),
+            );
+        }
     }
 
     out
@@ -130,77 +544,400 @@ pub fn split_romaji(text: &str) -> Vec<(String)> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use romaji::{to_romaji, try_to_romaji, Match};
+
+    fn tokens(text: &str) -> Vec<Syllable> {
+        tokenize(
+            text,
+            RomanizationSystem::ModifiedHepburn,
+            LongVowels::Doubled,
+        )
+    }
+
+    fn split(text: &str) -> Vec<String> {
+        split_romaji(text, RomanizationSystem::ModifiedHepburn, LongVowels::Doubled)
+    }
 
     #[test]
     fn test_split_romaji_basic_cases() {
         // empty string
-        assert_eq!(split_romaji(""), Vec::<String>::new());
+        assert_eq!(split(""), Vec::<&str>::new());
 
         // non-kana passthrough
-        assert_eq!(split_romaji("abc"), vec!("a", "b", "c"));
+        assert_eq!(split("abc"), vec!("a", "b", "c"));
 
         // hiragana (basic)
         assert_eq!(
-            split_romaji("あそび あそばせ"),
+            split("あそび あそばせ"),
             vec!("a", "so", "bi", " ", "a", "so", "ba", "se")
         );
 
         // katakana (basic)
         assert_eq!(
-            split_romaji("アソビ アソバセ"),
+            split("アソビ アソバセ"),
             vec!("a", "so", "bi", " ", "a", "so", "ba", "se")
         );
 
         // small tsu
-        assert_eq!(split_romaji("だって"), vec!("da", "t", "te"));
-        assert_eq!(split_romaji("ダッテ"), vec!("da", "t", "te"));
+        assert_eq!(split("だって"), vec!("da", "t", "te"));
+        assert_eq!(split("ダッテ"), vec!("da", "t", "te"));
 
         // long bar
-        assert_eq!(split_romaji("ハーハー"), vec!("ha", "a", "ha", "a"));
+        assert_eq!(split("ハーハー"), vec!("ha", "a", "ha", "a"));
 
         // digraphs (basic)
         assert_eq!(
-            split_romaji("きゃにゅびょ"),
+            split("きゃにゅびょ"),
             vec!("k", "ya", "n", "yu", "b", "yo")
         );
         assert_eq!(
-            split_romaji("キャニュビョ"),
+            split("キャニュビョ"),
             vec!("k", "ya", "n", "yu", "b", "yo")
         );
 
         // digraphs (ch, sh, j)
         assert_eq!(
-            split_romaji("しゃじゃちゃぢゃシェフュ"),
-            vec!("sh", "a", "j", "a", "ch", "a", "dj", "a", "sh", "e", "f", "yu")
+            split("しゃじゃちゃぢゃシェフュ"),
+            vec!("sh", "a", "j", "a", "ch", "a", "j", "a", "sh", "e", "f", "yu")
         );
 
         // digraph with replacement
-        assert_eq!(split_romaji("イョ"), vec!("y", "o"));
-        assert_eq!(split_romaji("ヴァ"), vec!("v", "a"));
-        assert_eq!(split_romaji("ヴア"), vec!("vu", "a"));
-        assert_eq!(split_romaji("ヴヴ"), vec!("vu", "vu"));
-        assert_eq!(split_romaji("クォ"), vec!("kw", "o"));
+        assert_eq!(split("イョ"), vec!("y", "o"));
+        assert_eq!(split("ヴァ"), vec!("v", "a"));
+        assert_eq!(split("ヴア"), vec!("vu", "a"));
+        assert_eq!(split("ヴヴ"), vec!("vu", "vu"));
+        assert_eq!(split("クォ"), vec!("kw", "o"));
+        assert_eq!(split("クヮ"), vec!("kw", "a"));
+        assert_eq!(split("キヮ"), vec!("k", "wa"));
+
+        // hiragana digraphs with small vowels
+        assert_eq!(split("うぃ"), vec!("w", "i"));
+        assert_eq!(split("いぇ"), vec!("y", "e"));
+        assert_eq!(split("くぉ"), vec!("kw", "o"));
+        assert_eq!(split("ゔぁ"), vec!("v", "a"));
+        assert_eq!(split("とうぃ"), vec!("to", "w", "i"));
+
+        // iteration marks
+        assert_eq!(split("こゝろ"), vec!("ko", "ko", "ro"));
+        assert_eq!(split("いすゞ"), vec!("i", "su", "zu"));
+        assert_eq!(split("ぶゝ"), vec!("bu", "fu"));
+        assert_eq!(split("ハヽヾ"), vec!("ha", "ha", "ba"));
+        assert_eq!(split("みすゞゝ"), vec!("mi", "su", "zu", "su"));
+        assert_eq!(split("ゝ あゝ"), vec!("ゝ", " ", "a", "a"));
+        assert_eq!(split("あ ゝ"), vec!("a", " ", "ゝ"));
+        assert_eq!(split("きゃゝ"), vec!("k", "ya", "kya"));
+        assert_eq!(split("しゅゞ"), vec!("sh", "u", "ju"));
+        assert_eq!(split("キャッヽ"), vec!("k", "ya", "k", "kya"));
+
+        // historical and small kana
+        assert_eq!(split("ゐゑヰヱ"), vec!("i", "e", "i", "e"));
+        assert_eq!(split("ヷヸヹヺ"), vec!("va", "vi", "ve", "vo"));
+        assert_eq!(split("ヵヶゕゖ"), vec!("ka", "ke", "ka", "ke"));
+    }
+
+    #[test]
+    fn test_split_romaji_systems() {
+        let text = "しゃしんぶんちゅうもん ぢゃづをっちぇ パーティー";
+        for &system in RomanizationSystem::ALL {
+            let split = split_romaji(text, system, LongVowels::Doubled);
+            assert_eq!(split.len(), text.chars().count(), "{:?}", system);
+        }
+
+        assert_eq!(
+            split_romaji(
+                "しゃっちぇ",
+                RomanizationSystem::KunreiShiki,
+                LongVowels::Doubled
+            ),
+            vec!("s", "ya", "t", "t", "ye")
+        );
+        assert_eq!(
+            split_romaji(
+                "ぢゃんぶ",
+                RomanizationSystem::TraditionalHepburn,
+                LongVowels::Doubled
+            ),
+            vec!("j", "a", "m", "bu")
+        );
+        assert_eq!(
+            split_romaji("ぢゃんー", RomanizationSystem::Wapuro, LongVowels::Doubled),
+            vec!("d", "ya", "nn", "-")
+        );
+        assert_eq!(
+            split_romaji("んゃ", RomanizationSystem::Wapuro, LongVowels::Doubled),
+            vec!("nn", "xya")
+        );
+        assert_eq!(split("んゃ"), vec!("n", "xya"));
+    }
+
+    #[test]
+    fn test_split_romaji_accepted() {
+        fn accepted(text: &str) -> Vec<Vec<String>> {
+            tokens(text).into_iter().map(|x| x.accepted).collect()
+        }
+
+        assert_eq!(accepted("か"), vec![vec!["ka"]]);
+        assert_eq!(accepted("し"), vec![vec!["shi", "si"]]);
+        assert_eq!(accepted("つ"), vec![vec!["tsu", "tu"]]);
+        assert_eq!(accepted("ふ"), vec![vec!["fu", "hu"]]);
+        assert_eq!(accepted("ぢ"), vec![vec!["ji", "zi", "di", "dji"]]);
+        assert_eq!(accepted("づ"), vec![vec!["zu", "du", "dzu"]]);
+        assert_eq!(accepted("を"), vec![vec!["o", "wo"]]);
+        assert_eq!(accepted("ゐ"), vec![vec!["i", "wi"]]);
+        assert_eq!(accepted("ゑ"), vec![vec!["e", "we"]]);
+        assert_eq!(accepted("しゃ"), vec![vec!["sh", "sy"], vec!["a"]]);
+        assert_eq!(accepted("きゃ"), vec![vec!["k"], vec!["ya"]]);
+        assert_eq!(accepted("ぢゃ"), vec![vec!["j", "zy", "dy", "dj"], vec!["a"]]);
+        assert_eq!(
+            accepted("っち"),
+            vec![vec!["t"], vec!["chi", "ti"]]
+        );
+        assert_eq!(
+            accepted("んば"),
+            vec![vec!["n", "m", "nn", "n'"], vec!["ba"]]
+        );
+    }
+
+    #[test]
+    fn test_split_romaji_syllabic_n() {
+        assert_eq!(split("きんえん"), vec!("ki", "n'", "e", "n"));
+        assert_eq!(split("きねん"), vec!("ki", "ne", "n"));
+        assert_eq!(split("こんや"), vec!("ko", "n'", "ya"));
+        assert_eq!(split("こにゃ"), vec!("ko", "n", "ya"));
+        assert_eq!(split("ほんを"), vec!("ho", "n'", "o"));
+        assert_eq!(split("ほんね"), vec!("ho", "n", "ne"));
+
+        assert_eq!(
+            split_romaji(
+                "きんえん",
+                RomanizationSystem::TraditionalHepburn,
+                LongVowels::Doubled
+            ),
+            vec!("ki", "n-", "e", "n")
+        );
+
+        let accepted = tokens("きんえん").remove(1).accepted;
+        assert_eq!(accepted, vec!["n'", "n-", "nn"]);
+    }
+
+    #[test]
+    fn test_split_romaji_long_vowels() {
+        fn split_long(text: &str, long: LongVowels) -> Vec<String> {
+            split_romaji(text, RomanizationSystem::ModifiedHepburn, long)
+        }
+
+        let m = &MACRON.to_string()[..];
+        let c = &CIRCUMFLEX.to_string()[..];
+
+        assert_eq!(split("とうきょう"), vec!("to", "u", "k", "yo", "u"));
+        assert_eq!(
+            split_long("とうきょう", LongVowels::Macron),
+            vec!("to", m, "k", "yo", m)
+        );
+        assert_eq!(
+            split_long("とうきょう", LongVowels::Circumflex),
+            vec!("to", c, "k", "yo", c)
+        );
+        assert_eq!(
+            split_long("とうきょう", LongVowels::Ou),
+            vec!("to", "u", "k", "yo", "u")
+        );
+        assert_eq!(
+            split_long("とうきょう", LongVowels::Oo),
+            vec!("to", "o", "k", "yo", "o")
+        );
+        assert_eq!(
+            split_long("コーヒー", LongVowels::Ou),
+            vec!("ko", "u", "hi", "i")
+        );
+        assert_eq!(
+            split_long("コーヒー", LongVowels::Macron),
+            vec!("ko", m, "hi", m)
+        );
+        assert_eq!(
+            split_long("おおきい", LongVowels::Macron),
+            vec!("o", m, "ki", "i")
+        );
+        assert_eq!(
+            split_long("せんせい", LongVowels::Macron),
+            vec!("se", "n", "se", "i")
+        );
+
+        let accepted = tokens("せんせい").pop().unwrap().accepted;
+        assert_eq!(accepted, vec!["i", m, c]);
+
+        let accepted = tokens("パー").pop().unwrap().accepted;
+        assert_eq!(accepted, vec!["a", "-", m, c]);
+    }
+
+    #[test]
+    fn test_tokenize_kinds() {
+        use self::SyllableKind::*;
+
+        fn kinds(text: &str) -> Vec<SyllableKind> {
+            tokens(text).into_iter().map(|x| x.kind).collect()
+        }
+
+        assert_eq!(kinds("かっきゃ"), vec![Plain, Sokuon, Digraph, Digraph]);
+        assert_eq!(
+            kinds("とう ター"),
+            vec![Plain, LongVowel, NonKana, Plain, LongVowel]
+        );
+        assert_eq!(
+            kinds("っゃかっ"),
+            vec![
+                Error(SyllableError::Sokuon),
+                Error(SyllableError::SmallKana),
+                Plain,
+                Error(SyllableError::Sokuon)
+            ]
+        );
+        assert_eq!(kinds("ゝ"), vec![Error(SyllableError::IterationMark)]);
+        assert_eq!(split("っゃかっ!"), vec!("xtsu", "xya", "ka", "xtsu", "!"));
+    }
+
+    #[test]
+    fn test_tokenize_range() {
+        fn ranges(text: &str) -> Vec<Range<usize>> {
+            tokens(text).into_iter().map(|x| x.range).collect()
+        }
+
+        assert_eq!(ranges("きゃっ"), vec![0..1, 1..2, 2..3]);
+        assert_eq!(ranges("ｷﾞｬｯ"), vec![0..2, 2..3, 3..4]);
+    }
+
+    #[test]
+    fn test_try_split_romaji() {
+        let system = RomanizationSystem::ModifiedHepburn;
+        let long = LongVowels::Doubled;
+        assert_eq!(
+            try_split_romaji("きゃっ", system, long),
+            Err(ConversionError {
+                position: 2..3,
+                reason: SyllableError::Sokuon,
+            })
+        );
+        assert_eq!(
+            try_split_romaji("か ゃ", system, long),
+            Err(ConversionError {
+                position: 2..3,
+                reason: SyllableError::SmallKana,
+            })
+        );
+        assert_eq!(
+            try_split_romaji("ッャ", system, long).unwrap_err().position,
+            0..1
+        );
+        assert_eq!(
+            try_split_romaji("きゃっと", system, long),
+            Ok(vec!["k", "ya", "t", "to"]
+                .into_iter()
+                .map(String::from)
+                .collect())
+        );
+    }
+
+    // Characters used by the exhaustive tests: the whole table plus
+    // some characters that interact with the kana.
+    fn all_chars() -> Vec<char> {
+        TABLE
+            .iter()
+            .map(|x| x.get_char())
+            .chain(" a\u{3099}ﾞ".chars())
+            .collect()
+    }
+
+    // A few characters for each `Kana` variant in each script, plus
+    // the characters with special handling. Used by the triples test,
+    // where the whole table would be too slow.
+    fn some_chars() -> Vec<char> {
+        let mut out: Vec<char> = Vec::new();
+        for kana in TABLE {
+            let same = |x: &Kana| {
+                std::mem::discriminant(x) == std::mem::discriminant(kana)
+                    && x.script() == kana.script()
+            };
+            if TABLE.iter().take_while(|x| *x != kana).filter(|x| same(x)).count() < 3 {
+                out.push(kana.get_char());
+            }
+        }
+        out.extend("んンいうしじちふ a\u{3099}ﾞ".chars());
+        out
+    }
+
+    // Tokenizing must never panic and must keep one syllable per
+    // normalized character. The public conversions must agree with
+    // the tokens, and the canonical romaji must match the kana.
+    fn check_tokenize(text: &str, system: RomanizationSystem) {
+        let count = normalize(text).text.chars().count();
+        let result = tokenize(text, system, LongVowels::Macron);
+        assert_eq!(result.len(), count, "{}", text);
+        for (i, syllable) in result.iter().enumerate() {
+            assert!(!syllable.romaji.is_empty(), "{} at {}", text, i);
+        }
+
+        let romaji = to_romaji(text, system, LongVowels::Macron);
+        match try_to_romaji(text, system, LongVowels::Macron) {
+            Ok(value) => assert_eq!(value, romaji, "{}", text),
+            Err(err) => {
+                let kind = SyllableKind::Error(err.reason);
+                assert!(result.iter().any(|x| x.kind == kind), "{}", text);
+            }
+        }
+
+        if system == RomanizationSystem::default() {
+            let romaji = to_romaji(text, system, LongVowels::default());
+            let m = Match::new(text, &romaji);
+            assert!(m.is_match, "{} as {}", text, romaji);
+            assert_eq!(m.chars.len(), count, "{}", text);
+        }
+    }
+
+    #[test]
+    fn test_tokenize_all_pairs() {
+        let chars = all_chars();
+        for &a in &chars {
+            for &b in &chars {
+                let text: String = [a, b].iter().collect();
+                for &system in RomanizationSystem::ALL {
+                    check_tokenize(&text, system);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_tokenize_all_triples() {
+        let chars = some_chars();
+        for &a in &chars {
+            for &b in &chars {
+                for &c in &chars {
+                    let text: String = [a, b, c].iter().collect();
+                    check_tokenize(&text, RomanizationSystem::default());
+                }
+            }
+        }
     }
 
     #[test]
     fn test_tsu_with_digraph() {
-        assert_eq!(split_romaji("ッイョ"), vec!("y", "y", "o"));
-        assert_eq!(split_romaji("ッッイョ"), vec!("y", "y", "y", "o"));
-        assert_eq!(split_romaji("ハッイョ"), vec!("ha", "y", "y", "o"));
+        assert_eq!(split("ッイョ"), vec!("y", "y", "o"));
+        assert_eq!(split("ッッイョ"), vec!("y", "y", "y", "o"));
+        assert_eq!(split("ハッイョ"), vec!("ha", "y", "y", "o"));
         assert_eq!(
-            split_romaji("ハッッイョ"),
+            split("ハッッイョ"),
             vec!("ha", "y", "y", "y", "o")
         );
         assert_eq!(
-            split_romaji("ッハッッイョ"),
+            split("ッハッッイョ"),
             vec!("h", "ha", "y", "y", "y", "o")
         );
         assert_eq!(
-            split_romaji("ッハッッイイョ"),
+            split("ッハッッイイョ"),
             vec!("h", "ha", "i", "i", "i", "y", "o")
         );
         assert_eq!(
-            split_romaji("ッハッッイッッッイョ"),
+            split("ッハッッイッッッイョ"),
             vec!("h", "ha", "i", "i", "i", "y", "y", "y", "y", "o")
         );
     }
@@ -1,6 +1,8 @@
 use std::collections::hash_map::Entry;
 use std::collections::HashMap;
 
+use serde::{Deserialize, Serialize};
+
 /// Enumeration for hiragana and katakana characters.
 #[derive(Debug, Copy, Clone, PartialEq)]
 pub enum Kana {
@@ -13,6 +15,12 @@ pub enum Kana {
     /// Katakana long bar `ー`.
     Bar(char),
 
+    /// Iteration marks `ゝ` and `ヽ`, which repeat the previous kana,
+    /// and the voiced `ゞ` and `ヾ`.
+    ///
+    /// The second element is true for the voiced marks.
+    Iter(char, bool),
+
     /// A hiragana or katakana character that does not generate
     /// a custom digraph (i.e. the digraph is formed by the character
     /// own syllable).
@@ -41,12 +49,383 @@ impl Kana {
             Kana::SmallTsu(chr) => *chr,
             Kana::Small(chr, _) => *chr,
             Kana::Bar(chr) => *chr,
+            Kana::Iter(chr, _) => *chr,
             Kana::Chr(chr, _) => *chr,
             Kana::Dig(chr, _, _) => *chr,
         }
     }
+
+    /// The script for the character. The `ー` is considered katakana.
+    pub fn script(&self) -> Script {
+        let chr = self.get_char();
+        if ('\u{3040}'..='\u{309F}').contains(&chr) {
+            Script::Hiragana
+        } else {
+            Script::Katakana
+        }
+    }
+
+    /// Returns the same character in the given script, if any (e.g.
+    /// `カ` for `か`).
+    ///
+    /// The `ー` and the katakana `ヷ`, `ヸ`, `ヹ` and `ヺ` have no
+    /// hiragana equivalent.
+    pub fn to_script(&self, script: Script) -> Option<Kana> {
+        let chr = self.get_char();
+        if self.script() == script {
+            return Some(*self);
+        }
+        let code = chr as u32;
+        let code = match (chr, script) {
+            ('ぁ'..='ゖ', Script::Katakana) | ('ゝ'..='ゞ', Script::Katakana) => code + 0x60,
+            ('ァ'..='ヶ', Script::Hiragana) | ('ヽ'..='ヾ', Script::Hiragana) => code - 0x60,
+            _ => return None,
+        };
+        std::char::from_u32(code).and_then(Kana::get)
+    }
+
+    /// Row of the character in the gojūon table, ignoring voicing and
+    /// size (e.g. `Row::Ka` for `が` and `Row::Ya` for `ゃ`).
+    ///
+    /// Returns `None` for characters that are not in the table, such
+    /// as `ー` and the iteration marks.
+    pub fn row(&self) -> Option<Row> {
+        self.gojuon().map(|x| x.0)
+    }
+
+    /// Column of the character in the gojūon table, ignoring voicing
+    /// and size.
+    ///
+    /// Returns `None` for characters that are not in the table and for
+    /// `ん`, which has no column.
+    pub fn column(&self) -> Option<Column> {
+        self.gojuon().and_then(|x| x.1)
+    }
+
+    fn gojuon(&self) -> Option<(Row, Option<Column>)> {
+        let base = self
+            .to_large()
+            .without_dakuten()
+            .to_script(Script::Hiragana)?;
+        let base = base.get_char();
+        for (row, kana) in Row::ALL.iter().zip(GOJUON) {
+            if let Some(index) = kana.chars().position(|x| x == base) {
+                let column = Column::ALL.get(index).cloned().filter(|_| *row != Row::N);
+                return Some((*row, column));
+            }
+        }
+        None
+    }
+
+    /// Voicing of the character.
+    pub fn voicing(&self) -> Voicing {
+        let chr = self.get_char();
+        if unvoiced(chr) != chr {
+            Voicing::Voiced
+        } else if unsemivoiced(chr) != chr {
+            Voicing::SemiVoiced
+        } else {
+            Voicing::Unvoiced
+        }
+    }
+
+    /// Returns the character with the dakuten added (e.g. `が` for
+    /// `か` or `ば` for `ぱ`), if there is one.
+    pub fn with_dakuten(&self) -> Option<Kana> {
+        let chr = self.without_dakuten().get_char();
+        Some(voiced(chr)).filter(|&x| x != chr).and_then(Kana::get)
+    }
+
+    /// Returns the character with the handakuten added (e.g. `ぱ` for
+    /// `は` or `ば`), if there is one.
+    pub fn with_handakuten(&self) -> Option<Kana> {
+        let chr = self.without_dakuten().get_char();
+        Some(semivoiced(chr))
+            .filter(|&x| x != chr)
+            .and_then(Kana::get)
+    }
+
+    /// Returns the character with any dakuten or handakuten removed
+    /// (e.g. `か` for `が`).
+    pub fn without_dakuten(&self) -> Kana {
+        let chr = self.get_char();
+        let base = unsemivoiced(unvoiced(chr));
+        Kana::get(base).unwrap_or(*self)
+    }
+
+    /// True for the small kana, including `っ` and `ゕ`.
+    pub fn is_small(&self) -> bool {
+        SMALL.iter().any(|x| x.0 == self.get_char())
+    }
+
+    /// True for the small kana that form the second half of a digraph
+    /// (e.g. `ゃ` and `ァ`).
+    pub fn is_digraph_suffix(&self) -> bool {
+        matches!(self, Kana::Small(_, _))
+    }
+
+    /// Returns the large version of a small kana (e.g. `や` for
+    /// `ゃ`). Any other character is returned as is.
+    pub fn to_large(&self) -> Kana {
+        let chr = self.get_char();
+        SMALL
+            .iter()
+            .find(|x| x.0 == chr)
+            .and_then(|x| Kana::get(x.1))
+            .unwrap_or(*self)
+    }
+
+    /// Returns the small version of a kana (e.g. `ゃ` for `や`), if
+    /// there is one.
+    pub fn to_small(&self) -> Option<Kana> {
+        let chr = self.get_char();
+        if self.is_small() {
+            return Some(*self);
+        }
+        SMALL
+            .iter()
+            .find(|x| x.1 == chr)
+            .and_then(|x| Kana::get(x.0))
+    }
+}
+
+/// Script for a kana character.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Script {
+    Hiragana,
+    Katakana,
+}
+
+/// Voicing of a kana character.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Voicing {
+    /// No voicing mark (e.g. `か`).
+    Unvoiced,
+    /// Voiced with a dakuten (e.g. `が`).
+    Voiced,
+    /// Semi-voiced with a handakuten (e.g. `ぱ`).
+    SemiVoiced,
+}
+
+/// Row in the gojūon table.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Row {
+    A,
+    Ka,
+    Sa,
+    Ta,
+    Na,
+    Ha,
+    Ma,
+    Ya,
+    Ra,
+    Wa,
+    /// The syllabic `ん`.
+    N,
+}
+
+impl Row {
+    /// All rows in the table order.
+    pub const ALL: &'static [Row] = &[
+        Row::A,
+        Row::Ka,
+        Row::Sa,
+        Row::Ta,
+        Row::Na,
+        Row::Ha,
+        Row::Ma,
+        Row::Ya,
+        Row::Ra,
+        Row::Wa,
+        Row::N,
+    ];
+}
+
+/// Column (vowel) in the gojūon table.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Column {
+    A,
+    I,
+    U,
+    E,
+    O,
+}
+
+impl Column {
+    /// All columns in the table order.
+    pub const ALL: &'static [Column] = &[Column::A, Column::I, Column::U, Column::E, Column::O];
+}
+
+/// The gojūon table in hiragana, with one entry per `Row`. Gaps in
+/// the table are marked by a `・`.
+static GOJUON: &[&str] = &[
+    "あいうえお",
+    "かきくけこ",
+    "さしすせそ",
+    "たちつてと",
+    "なにぬねの",
+    "はひふへほ",
+    "まみむめも",
+    "や・ゆ・よ",
+    "らりるれろ",
+    "わゐ・ゑを",
+    "ん",
+];
+
+/// Category of an entry in the gojūon table.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Category {
+    /// The basic kana (e.g. `か`), including `ん`.
+    Basic,
+    /// Voiced kana with a dakuten (e.g. `が`).
+    Voiced,
+    /// Semi-voiced kana with a handakuten (e.g. `ぱ`).
+    SemiVoiced,
+    /// Yōon digraphs with a small `ゃ`, `ゅ` or `ょ` (e.g. `きゃ`).
+    Yoon,
+    /// Historical kana that are not used in modern text (`ゐ` and
+    /// `ゑ`).
+    Historical,
+}
+
+impl Category {
+    /// All categories.
+    pub const ALL: &'static [Category] = &[
+        Category::Basic,
+        Category::Voiced,
+        Category::SemiVoiced,
+        Category::Yoon,
+        Category::Historical,
+    ];
+}
+
+/// An entry in the gojūon table returned by `gojuon`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GojuonEntry {
+    /// The kana in hiragana. This has two characters for yōon.
+    pub kana: String,
+
+    /// Row of the entry. Voiced and yōon entries are in the row of
+    /// their base kana (e.g. `ぎゃ` is in `Row::Ka`).
+    pub row: Row,
+
+    /// Column of the entry. For yōon this is the vowel of the small
+    /// kana (e.g. `Column::U` for `きゅ`). `ん` has no column.
+    pub column: Option<Column>,
+
+    /// Category of the entry.
+    pub category: Category,
+}
+
+impl GojuonEntry {
+    /// The kana for the entry in the given script.
+    pub fn kana(&self, script: Script) -> String {
+        self.kana
+            .chars()
+            .filter_map(Kana::get)
+            .filter_map(|x| x.to_script(script))
+            .map(|x| x.get_char())
+            .collect()
+    }
+}
+
+/// Returns the gojūon table, including the voiced, semi-voiced, yōon
+/// and historical entries, in table order.
+pub fn gojuon() -> &'static [GojuonEntry] {
+    &GOJUON_TABLE
+}
+
+lazy_static! {
+    static ref GOJUON_TABLE: Vec<GojuonEntry> = {
+        let mut out = Vec::new();
+        let mut push = |kana: String, row: Row, column: Option<Column>, category: Category| {
+            out.push(GojuonEntry {
+                kana,
+                row,
+                column,
+                category,
+            });
+        };
+
+        for (&row, line) in Row::ALL.iter().zip(GOJUON) {
+            for (index, chr) in line.chars().enumerate() {
+                if chr == '・' {
+                    continue;
+                }
+                let column = Column::ALL.get(index).cloned().filter(|_| row != Row::N);
+                let category = if "ゐゑ".contains(chr) {
+                    Category::Historical
+                } else {
+                    Category::Basic
+                };
+                push(chr.to_string(), row, column, category);
+            }
+
+            // Voiced variants are only considered for the `k`, `s`,
+            // `t` and `h` rows (e.g. `ゔ` is not in the table).
+            if let Row::Ka | Row::Sa | Row::Ta | Row::Ha = row {
+                for (index, chr) in line.chars().enumerate() {
+                    push(voiced(chr).to_string(), row, Column::ALL.get(index).cloned(), Category::Voiced);
+                }
+            }
+            if row == Row::Ha {
+                for (index, chr) in line.chars().enumerate() {
+                    let kana = semivoiced(chr).to_string();
+                    push(kana, row, Column::ALL.get(index).cloned(), Category::SemiVoiced);
+                }
+            }
+
+            // Yōon are formed by the `i` column, except for `い`.
+            if let Row::A | Row::Ya | Row::Wa | Row::N = row {
+                continue;
+            }
+            let base = line.chars().nth(1).unwrap();
+            let mut bases = vec![base];
+            if voiced(base) != base && row != Row::Ta {
+                bases.push(voiced(base));
+            }
+            if semivoiced(base) != base {
+                bases.push(semivoiced(base));
+            }
+            for base in bases {
+                for &(small, column) in &[('ゃ', Column::A), ('ゅ', Column::U), ('ょ', Column::O)] {
+                    let kana: String = [base, small].iter().collect();
+                    push(kana, row, Some(column), Category::Yoon);
+                }
+            }
+        }
+        out
+    };
 }
 
+/// Small kana and their large version.
+static SMALL: &[(char, char)] = &[
+    ('ぁ', 'あ'),
+    ('ぃ', 'い'),
+    ('ぅ', 'う'),
+    ('ぇ', 'え'),
+    ('ぉ', 'お'),
+    ('っ', 'つ'),
+    ('ゃ', 'や'),
+    ('ゅ', 'ゆ'),
+    ('ょ', 'よ'),
+    ('ゎ', 'わ'),
+    ('ゕ', 'か'),
+    ('ゖ', 'け'),
+    ('ァ', 'ア'),
+    ('ィ', 'イ'),
+    ('ゥ', 'ウ'),
+    ('ェ', 'エ'),
+    ('ォ', 'オ'),
+    ('ッ', 'ツ'),
+    ('ャ', 'ヤ'),
+    ('ュ', 'ユ'),
+    ('ョ', 'ヨ'),
+    ('ヮ', 'ワ'),
+    ('ヵ', 'カ'),
+    ('ヶ', 'ケ'),
+];
+
 // Digraph suffixes for the small variants of kana characters.
 #[derive(Debug, Copy, Clone, PartialEq)]
 pub enum DigraphSuffix {
@@ -58,6 +437,7 @@ pub enum DigraphSuffix {
     U,
     E,
     O,
+    Wa,
 }
 
 impl DigraphSuffix {
@@ -71,6 +451,7 @@ impl DigraphSuffix {
             DigraphSuffix::U => "u",
             DigraphSuffix::E => "e",
             DigraphSuffix::O => "o",
+            DigraphSuffix::Wa => "wa",
         }
     }
 }
@@ -95,7 +476,221 @@ impl DigraphPrefix {
     }
 }
 
-static TABLE: &[Kana] = &[
+/// Romanization systems supported when converting kana to romaji.
+///
+/// The romaji in `TABLE` is not bound to any particular system and
+/// must be translated with `syllable`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+pub enum RomanizationSystem {
+    /// Modified Hepburn (e.g. `shi`, `chi`, `tsu`, `ji` and `o` for
+    /// `を`). This is the default.
+    #[default]
+    ModifiedHepburn,
+
+    /// Traditional Hepburn. Same as modified Hepburn, but with `wo`
+    /// for `を` and `m` for `ん` before `b`, `m` and `p`.
+    TraditionalHepburn,
+
+    /// Kunrei-shiki (e.g. `si`, `ti`, `tu`, `zi` and `zu` for `づ`).
+    KunreiShiki,
+
+    /// Nihon-shiki. Same as Kunrei-shiki, but with `di`, `du` and
+    /// `wo` for `ぢ`, `づ` and `を`.
+    NihonShiki,
+
+    /// Wāpuro romaji, as used for typing on keyboards (e.g. `shi`,
+    /// `di`, `du`, `wo` and `nn` for `ん`).
+    Wapuro,
+}
+
+impl RomanizationSystem {
+    /// All the supported systems.
+    pub const ALL: &'static [RomanizationSystem] = &[
+        RomanizationSystem::ModifiedHepburn,
+        RomanizationSystem::TraditionalHepburn,
+        RomanizationSystem::KunreiShiki,
+        RomanizationSystem::NihonShiki,
+        RomanizationSystem::Wapuro,
+    ];
+
+    /// Translate a romaji syllable from `TABLE` to this system.
+    pub fn syllable(self, romaji: &'static str) -> &'static str {
+        use self::RomanizationSystem::*;
+        match (self, romaji) {
+            (ModifiedHepburn, "wo") => "o",
+            (ModifiedHepburn, "wi") | (TraditionalHepburn, "wi") | (KunreiShiki, "wi") => "i",
+            (ModifiedHepburn, "we") | (TraditionalHepburn, "we") | (KunreiShiki, "we") => "e",
+            (ModifiedHepburn, "dji") | (TraditionalHepburn, "dji") => "ji",
+            (ModifiedHepburn, "dzu") | (TraditionalHepburn, "dzu") => "zu",
+            (KunreiShiki, "shi") | (NihonShiki, "shi") => "si",
+            (KunreiShiki, "chi") | (NihonShiki, "chi") => "ti",
+            (KunreiShiki, "tsu") | (NihonShiki, "tsu") => "tu",
+            (KunreiShiki, "fu") | (NihonShiki, "fu") => "hu",
+            (KunreiShiki, "ji") | (NihonShiki, "ji") => "zi",
+            (KunreiShiki, "dji") => "zi",
+            (KunreiShiki, "dzu") => "zu",
+            (KunreiShiki, "wo") => "o",
+            (NihonShiki, "dji") | (Wapuro, "dji") => "di",
+            (NihonShiki, "dzu") | (Wapuro, "dzu") => "du",
+            (Wapuro, "n") => "nn",
+            _ => romaji,
+        }
+    }
+}
+
+/// Spelling used for long vowels when converting kana to romaji.
+///
+/// Long vowels are the katakana `ー` and, in hiragana, a vowel that
+/// extends the previous syllable (e.g. `おう` and `ああ`).
+///
+/// Words are not split into morphemes, so a vowel is also read as long
+/// across a morpheme boundary (e.g. `おもう` is spelled `omō` instead
+/// of `omou` with `Macron`). Answers are matched with any spelling,
+/// so both are accepted.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+pub enum LongVowels {
+    /// Spell the long vowels as written (e.g. `paatii` for `パーティー`
+    /// and `toukyou` for `とうきょう`). This is the default.
+    #[default]
+    Doubled,
+
+    /// Use macrons (e.g. `pātī` and `tōkyō`). As in Hepburn, `いい`
+    /// and `えい` are kept as `ii` and `ei`.
+    Macron,
+
+    /// Use circumflexes (e.g. `pâtî` and `tôkyô`), as in Kunrei-shiki
+    /// and Nihon-shiki.
+    Circumflex,
+
+    /// Same as `Doubled`, but a long `o` is always `ou` (e.g. `koohii`
+    /// for `コーヒー` is `kouhii`).
+    Ou,
+
+    /// Same as `Doubled`, but a long `o` is always `oo` (e.g.
+    /// `tookyoo`).
+    Oo,
+}
+
+impl LongVowels {
+    /// All the supported long vowel spellings.
+    pub const ALL: &'static [LongVowels] = &[
+        LongVowels::Doubled,
+        LongVowels::Macron,
+        LongVowels::Circumflex,
+        LongVowels::Ou,
+        LongVowels::Oo,
+    ];
+}
+
+/// Combining macron used for long vowels.
+pub const MACRON: char = '\u{0304}';
+
+/// Combining circumflex used for long vowels.
+pub const CIRCUMFLEX: char = '\u{0302}';
+
+/// Precomposed vowels with the respective vowel and combining mark.
+pub static ACCENTS: &[(char, char, char)] = &[
+    ('ā', 'a', MACRON),
+    ('ī', 'i', MACRON),
+    ('ū', 'u', MACRON),
+    ('ē', 'e', MACRON),
+    ('ō', 'o', MACRON),
+    ('â', 'a', CIRCUMFLEX),
+    ('î', 'i', CIRCUMFLEX),
+    ('û', 'u', CIRCUMFLEX),
+    ('ê', 'e', CIRCUMFLEX),
+    ('ô', 'o', CIRCUMFLEX),
+];
+
+/// Unvoiced kana and their voiced (dakuten) form.
+static VOICED: &[(char, char)] = &[
+    ('か', 'が'),
+    ('き', 'ぎ'),
+    ('く', 'ぐ'),
+    ('け', 'げ'),
+    ('こ', 'ご'),
+    ('さ', 'ざ'),
+    ('し', 'じ'),
+    ('す', 'ず'),
+    ('せ', 'ぜ'),
+    ('そ', 'ぞ'),
+    ('た', 'だ'),
+    ('ち', 'ぢ'),
+    ('つ', 'づ'),
+    ('て', 'で'),
+    ('と', 'ど'),
+    ('は', 'ば'),
+    ('ひ', 'び'),
+    ('ふ', 'ぶ'),
+    ('へ', 'べ'),
+    ('ほ', 'ぼ'),
+    ('う', 'ゔ'),
+    ('カ', 'ガ'),
+    ('キ', 'ギ'),
+    ('ク', 'グ'),
+    ('ケ', 'ゲ'),
+    ('コ', 'ゴ'),
+    ('サ', 'ザ'),
+    ('シ', 'ジ'),
+    ('ス', 'ズ'),
+    ('セ', 'ゼ'),
+    ('ソ', 'ゾ'),
+    ('タ', 'ダ'),
+    ('チ', 'ヂ'),
+    ('ツ', 'ヅ'),
+    ('テ', 'デ'),
+    ('ト', 'ド'),
+    ('ハ', 'バ'),
+    ('ヒ', 'ビ'),
+    ('フ', 'ブ'),
+    ('ヘ', 'ベ'),
+    ('ホ', 'ボ'),
+    ('ウ', 'ヴ'),
+    ('ワ', 'ヷ'),
+    ('ヰ', 'ヸ'),
+    ('ヱ', 'ヹ'),
+    ('ヲ', 'ヺ'),
+];
+
+/// Returns the voiced form of a kana (e.g. `が` for `か`), or the
+/// kana itself if it has none.
+pub fn voiced(chr: char) -> char {
+    VOICED.iter().find(|x| x.0 == chr).map_or(chr, |x| x.1)
+}
+
+/// Returns the unvoiced form of a kana (e.g. `か` for `が`), or the
+/// kana itself if it is not voiced.
+pub fn unvoiced(chr: char) -> char {
+    VOICED.iter().find(|x| x.1 == chr).map_or(chr, |x| x.0)
+}
+
+/// Unvoiced kana and their semi-voiced (handakuten) form.
+static SEMIVOICED: &[(char, char)] = &[
+    ('は', 'ぱ'),
+    ('ひ', 'ぴ'),
+    ('ふ', 'ぷ'),
+    ('へ', 'ぺ'),
+    ('ほ', 'ぽ'),
+    ('ハ', 'パ'),
+    ('ヒ', 'ピ'),
+    ('フ', 'プ'),
+    ('ヘ', 'ペ'),
+    ('ホ', 'ポ'),
+];
+
+/// Returns the semi-voiced form of a kana (e.g. `ぱ` for `は`), or
+/// the kana itself if it has none.
+pub fn semivoiced(chr: char) -> char {
+    SEMIVOICED.iter().find(|x| x.0 == chr).map_or(chr, |x| x.1)
+}
+
+/// Returns the kana without the handakuten (e.g. `は` for `ぱ`), or
+/// the kana itself if it is not semi-voiced.
+pub fn unsemivoiced(chr: char) -> char {
+    SEMIVOICED.iter().find(|x| x.1 == chr).map_or(chr, |x| x.0)
+}
+
+pub static TABLE: &[Kana] = &[
     //
     // Katakana
     //
@@ -108,9 +703,12 @@ static TABLE: &[Kana] = &[
     Kana::Small('ゥ', DigraphSuffix::U),
     Kana::Small('ェ', DigraphSuffix::E),
     Kana::Small('ォ', DigraphSuffix::O),
+    Kana::Small('ヮ', DigraphSuffix::Wa),
     // Extra
     Kana::Chr('ヴ', "vu"),
     Kana::Bar('ー'),
+    Kana::Iter('ヽ', false),
+    Kana::Iter('ヾ', true),
     // A
     Kana::Chr('ア', "a"),
     Kana::Dig('イ', "i", DigraphPrefix::Y),
@@ -197,6 +795,15 @@ static TABLE: &[Kana] = &[
     Kana::Chr('ワ', "wa"),
     Kana::Chr('ヲ', "wo"),
     Kana::Chr('ン', "n"),
+    // Historical, V and small K
+    Kana::Chr('ヰ', "wi"),
+    Kana::Chr('ヱ', "we"),
+    Kana::Chr('ヷ', "va"),
+    Kana::Chr('ヸ', "vi"),
+    Kana::Chr('ヹ', "ve"),
+    Kana::Chr('ヺ', "vo"),
+    Kana::Chr('ヵ', "ka"),
+    Kana::Chr('ヶ', "ke"),
     //
     // Hiragana
     //
@@ -209,22 +816,27 @@ static TABLE: &[Kana] = &[
     Kana::Small('ぅ', DigraphSuffix::U),
     Kana::Small('ぇ', DigraphSuffix::E),
     Kana::Small('ぉ', DigraphSuffix::O),
+    Kana::Small('ゎ', DigraphSuffix::Wa),
+    // Extra
+    Kana::Chr('ゔ', "vu"),
+    Kana::Iter('ゝ', false),
+    Kana::Iter('ゞ', true),
     // A
     Kana::Chr('あ', "a"),
-    Kana::Chr('い', "i"),
-    Kana::Chr('う', "u"),
+    Kana::Dig('い', "i", DigraphPrefix::Y),
+    Kana::Dig('う', "u", DigraphPrefix::W),
     Kana::Chr('え', "e"),
     Kana::Chr('お', "o"),
     // KA
     Kana::Chr('か', "ka"),
     Kana::Chr('き', "ki"),
-    Kana::Chr('く', "ku"),
+    Kana::Dig('く', "ku", DigraphPrefix::K),
     Kana::Chr('け', "ke"),
     Kana::Chr('こ', "ko"),
     // GA
     Kana::Chr('が', "ga"),
     Kana::Chr('ぎ', "gi"),
-    Kana::Chr('ぐ', "gu"),
+    Kana::Dig('ぐ', "gu", DigraphPrefix::G),
     Kana::Chr('げ', "ge"),
     Kana::Chr('ご', "go"),
     // SA
@@ -295,6 +907,11 @@ static TABLE: &[Kana] = &[
     Kana::Chr('わ', "wa"),
     Kana::Chr('を', "wo"),
     Kana::Chr('ん', "n"),
+    // Historical and small K
+    Kana::Chr('ゐ', "wi"),
+    Kana::Chr('ゑ', "we"),
+    Kana::Chr('ゕ', "ka"),
+    Kana::Chr('ゖ', "ke"),
 ];
 
 lazy_static! {
@@ -302,7 +919,7 @@ lazy_static! {
         let mut m: HashMap<char, Kana> = HashMap::new();
         for val in TABLE {
             let key = val.get_char();
-            let mut entry = m.entry(key);
+            let entry = m.entry(key);
             if let Entry::Occupied(_) = entry {
                 panic!("Character '{}' duplicated in TABLE", key);
             }
@@ -322,5 +939,148 @@ mod tests {
         assert_eq!(Kana::get('ッ').unwrap(), Kana::SmallTsu('ッ'));
         assert_eq!(Kana::get('そ').unwrap(), Kana::Chr('そ', "so"));
         assert_eq!(Kana::get('x'), None);
+        assert_eq!(Kana::get('ゞ').unwrap(), Kana::Iter('ゞ', true));
+    }
+
+    #[test]
+    fn test_kana_script() {
+        let get = |chr| Kana::get(chr).unwrap();
+        assert_eq!(get('か').script(), Script::Hiragana);
+        assert_eq!(get('ゞ').script(), Script::Hiragana);
+        assert_eq!(get('カ').script(), Script::Katakana);
+        assert_eq!(get('ー').script(), Script::Katakana);
+        assert_eq!(get('か').to_script(Script::Katakana), Some(get('カ')));
+        assert_eq!(get('ョ').to_script(Script::Hiragana), Some(get('ょ')));
+        assert_eq!(get('ヾ').to_script(Script::Hiragana), Some(get('ゞ')));
+        assert_eq!(get('ヶ').to_script(Script::Hiragana), Some(get('ゖ')));
+        assert_eq!(get('ヺ').to_script(Script::Hiragana), None);
+        assert_eq!(get('ー').to_script(Script::Hiragana), None);
+        assert_eq!(get('ー').to_script(Script::Katakana), Some(get('ー')));
+    }
+
+    #[test]
+    fn test_kana_grid() {
+        let get = |chr| Kana::get(chr).unwrap();
+        assert_eq!(get('か').row(), Some(Row::Ka));
+        assert_eq!(get('ギ').row(), Some(Row::Ka));
+        assert_eq!(get('ぽ').row(), Some(Row::Ha));
+        assert_eq!(get('ゃ').row(), Some(Row::Ya));
+        assert_eq!(get('ッ').row(), Some(Row::Ta));
+        assert_eq!(get('ヴ').row(), Some(Row::A));
+        assert_eq!(get('ヺ').row(), Some(Row::Wa));
+        assert_eq!(get('ん').row(), Some(Row::N));
+        assert_eq!(get('ー').row(), None);
+        assert_eq!(get('ゝ').row(), None);
+
+        assert_eq!(get('き').column(), Some(Column::I));
+        assert_eq!(get('ゅ').column(), Some(Column::U));
+        assert_eq!(get('ヲ').column(), Some(Column::O));
+        assert_eq!(get('ゑ').column(), Some(Column::E));
+        assert_eq!(get('ん').column(), None);
+    }
+
+    #[test]
+    fn test_kana_voicing() {
+        let get = |chr| Kana::get(chr).unwrap();
+        assert_eq!(get('か').voicing(), Voicing::Unvoiced);
+        assert_eq!(get('が').voicing(), Voicing::Voiced);
+        assert_eq!(get('ぱ').voicing(), Voicing::SemiVoiced);
+        assert_eq!(get('ヴ').voicing(), Voicing::Voiced);
+
+        assert_eq!(get('か').with_dakuten(), Some(get('が')));
+        assert_eq!(get('ぱ').with_dakuten(), Some(get('ば')));
+        assert_eq!(get('ば').with_handakuten(), Some(get('ぱ')));
+        assert_eq!(get('か').with_handakuten(), None);
+        assert_eq!(get('な').with_dakuten(), None);
+        assert_eq!(get('ぼ').without_dakuten(), get('ほ'));
+        assert_eq!(get('ポ').without_dakuten(), get('ホ'));
+        assert_eq!(get('ヷ').without_dakuten(), get('ワ'));
+        assert_eq!(get('な').without_dakuten(), get('な'));
+    }
+
+    #[test]
+    fn test_kana_small() {
+        let get = |chr| Kana::get(chr).unwrap();
+        assert!(get('ゃ').is_small());
+        assert!(get('っ').is_small());
+        assert!(get('ヶ').is_small());
+        assert!(!get('や').is_small());
+        assert!(get('ゃ').is_digraph_suffix());
+        assert!(!get('っ').is_digraph_suffix());
+        assert_eq!(get('ゃ').to_large(), get('や'));
+        assert_eq!(get('ッ').to_large(), get('ツ'));
+        assert_eq!(get('か').to_large(), get('か'));
+        assert_eq!(get('わ').to_small(), Some(get('ゎ')));
+        assert_eq!(get('ケ').to_small(), Some(get('ヶ')));
+        assert_eq!(get('ゃ').to_small(), Some(get('ゃ')));
+        assert_eq!(get('さ').to_small(), None);
+    }
+
+    #[test]
+    fn test_gojuon() {
+        fn kana(row: Row, category: Category) -> Vec<&'static str> {
+            gojuon()
+                .iter()
+                .filter(|x| x.row == row && x.category == category)
+                .map(|x| x.kana.as_str())
+                .collect()
+        }
+
+        assert_eq!(
+            kana(Row::Ka, Category::Basic),
+            vec!["か", "き", "く", "け", "こ"]
+        );
+        assert_eq!(
+            kana(Row::Ka, Category::Voiced),
+            vec!["が", "ぎ", "ぐ", "げ", "ご"]
+        );
+        assert_eq!(
+            kana(Row::Ha, Category::SemiVoiced),
+            vec!["ぱ", "ぴ", "ぷ", "ぺ", "ぽ"]
+        );
+        assert_eq!(kana(Row::Ya, Category::Basic), vec!["や", "ゆ", "よ"]);
+        assert_eq!(kana(Row::Wa, Category::Basic), vec!["わ", "を"]);
+        assert_eq!(kana(Row::Wa, Category::Historical), vec!["ゐ", "ゑ"]);
+        assert_eq!(kana(Row::N, Category::Basic), vec!["ん"]);
+        assert_eq!(kana(Row::A, Category::Voiced), Vec::<&str>::new());
+        assert_eq!(
+            kana(Row::Sa, Category::Yoon),
+            vec!["しゃ", "しゅ", "しょ", "じゃ", "じゅ", "じょ"]
+        );
+        assert_eq!(kana(Row::Ta, Category::Yoon), vec!["ちゃ", "ちゅ", "ちょ"]);
+
+        let column: Vec<_> = gojuon()
+            .iter()
+            .filter(|x| x.column == Some(Column::U) && x.category == Category::Yoon)
+            .map(|x| x.kana(Script::Katakana))
+            .collect();
+        assert_eq!(
+            column,
+            vec![
+                "キュ", "ギュ", "シュ", "ジュ", "チュ", "ニュ", "ヒュ", "ビュ", "ピュ", "ミュ",
+                "リュ"
+            ]
+        );
+
+        // The entries must agree with the `Kana` classification.
+        for entry in gojuon() {
+            let kana = Kana::get(entry.kana.chars().next().unwrap()).unwrap();
+            assert_eq!(kana.row(), Some(entry.row), "{}", entry.kana);
+            if entry.category != Category::Yoon {
+                assert_eq!(kana.column(), entry.column, "{}", entry.kana);
+            }
+        }
+    }
+
+    #[test]
+    fn test_voiced() {
+        assert_eq!(voiced('か'), 'が');
+        assert_eq!(voiced('ヲ'), 'ヺ');
+        assert_eq!(voiced('が'), 'が');
+        assert_eq!(voiced('あ'), 'あ');
+        assert_eq!(unvoiced('ず'), 'す');
+        assert_eq!(unvoiced('す'), 'す');
+        assert_eq!(semivoiced('ほ'), 'ぽ');
+        assert_eq!(semivoiced('か'), 'か');
     }
 }
@@ -0,0 +1,179 @@
+use serde::{Deserialize, Serialize};
+
+use convert::try_to_hiragana;
+
+/// Keyboard layouts used to detect typos.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum Layout {
+    /// QWERTY (e.g. US and UK). This is the default.
+    #[default]
+    Qwerty,
+
+    /// AZERTY (e.g. French).
+    Azerty,
+
+    /// Dvorak simplified keyboard.
+    Dvorak,
+}
+
+impl Layout {
+    /// All the supported layouts.
+    pub const ALL: [Layout; 3] = [Layout::Qwerty, Layout::Azerty, Layout::Dvorak];
+
+    fn rows(self) -> [&'static str; 4] {
+        match self {
+            Layout::Qwerty => ["1234567890-", "qwertyuiop", "asdfghjkl;", "zxcvbnm,./"],
+            Layout::Azerty => ["1234567890-", "azertyuiop", "qsdfghjklm", "wxcvbn,;:!"],
+            Layout::Dvorak => ["1234567890[", "',.pyfgcrl", "aoeuidhtns", ";qjkxbmwvz"],
+        }
+    }
+
+    /// Returns the position of the key for the character, taking into
+    /// account the stagger between the rows.
+    fn position(self, chr: char) -> Option<(usize, f32)> {
+        const STAGGER: [f32; 4] = [0.0, 0.5, 0.75, 1.25];
+        let chr = chr.to_ascii_lowercase();
+        for (row, keys) in self.rows().iter().enumerate() {
+            if let Some(col) = keys.chars().position(|x| x == chr) {
+                return Some((row, col as f32 + STAGGER[row]));
+            }
+        }
+        None
+    }
+
+    /// True if the keys for the two characters are next to each other
+    /// in the layout, either in the same row or in the rows above and
+    /// below.
+    ///
+    /// ```
+    /// use kana::Layout;
+    ///
+    /// assert!(Layout::Qwerty.is_adjacent('a', 'q'));
+    /// assert!(!Layout::Qwerty.is_adjacent('a', 'p'));
+    /// assert!(Layout::Dvorak.is_adjacent('a', 'o'));
+    /// ```
+    pub fn is_adjacent(self, a: char, b: char) -> bool {
+        match (self.position(a), self.position(b)) {
+            (Some((r1, x1)), Some((r2, x2))) if a != b => {
+                let dx = (x1 - x2).abs();
+                if r1 == r2 {
+                    dx <= 1.0
+                } else {
+                    (r1 as isize - r2 as isize).abs() == 1 && dx < 1.0
+                }
+            }
+            _ => false,
+        }
+    }
+}
+
+/// Kind of error for a mistake in a `Match`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ErrorKind {
+    /// The text was mistyped (e.g. an adjacent key was hit).
+    Typo,
+
+    /// The kana was not recognized.
+    Recognition,
+}
+
+/// True if `text` is a likely typo for `expected` in the keyboard
+/// layout. This is the case when `text` differs from `expected` by:
+///
+/// - a single key that is adjacent to the expected one (e.g. `kq` for
+///   `ka` in QWERTY), unless `text` is itself a reading for some kana
+///   (e.g. `ku` for `ki` is a recognition error);
+/// - two consecutive keys that were swapped (e.g. `ak` for `ka`);
+/// - an extra key that is the same or adjacent to the key before or
+///   after it (e.g. `kka` or `kja` for `ka`).
+///
+/// ```
+/// use kana::{is_typo, Layout};
+///
+/// assert!(is_typo(Layout::Qwerty, "kq", "ka"));
+/// assert!(is_typo(Layout::Azerty, "kq", "ka"));
+/// assert!(!is_typo(Layout::Qwerty, "ki", "ka"));
+/// assert!(!is_typo(Layout::Qwerty, "ku", "ki"));
+/// ```
+pub fn is_typo(layout: Layout, text: &str, expected: &str) -> bool {
+    let a: Vec<char> = text.chars().collect();
+    let b: Vec<char> = expected.chars().collect();
+    if a.len() == b.len() {
+        let diff: Vec<usize> = (0..a.len()).filter(|&i| a[i] != b[i]).collect();
+        match diff.len() {
+            1 => layout.is_adjacent(a[diff[0]], b[diff[0]]) && try_to_hiragana(text).is_err(),
+            2 => {
+                let (i, j) = (diff[0], diff[1]);
+                j == i + 1 && a[i] == b[j] && a[j] == b[i]
+            }
+            _ => false,
+        }
+    } else if a.len() == b.len() + 1 {
+        (0..a.len()).any(|i| {
+            let mut rest = a.clone();
+            let extra = rest.remove(i);
+            rest == b && is_extra_key(layout, extra, &rest, i)
+        })
+    } else {
+        false
+    }
+}
+
+/// True if the character, inserted at `index` in `text`, is the same
+/// or adjacent to the key before or after it.
+pub(crate) fn is_extra_key(layout: Layout, chr: char, text: &[char], index: usize) -> bool {
+    let before = if index > 0 { text.get(index - 1) } else { None };
+    let after = text.get(index);
+    before
+        .iter()
+        .chain(after.iter())
+        .any(|&&x| x == chr || layout.is_adjacent(x, chr))
+}
+
+// spell-checker: disable
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_adjacent() {
+        assert!(Layout::Qwerty.is_adjacent('s', 'a'));
+        assert!(Layout::Qwerty.is_adjacent('s', 'w'));
+        assert!(Layout::Qwerty.is_adjacent('s', 'e'));
+        assert!(Layout::Qwerty.is_adjacent('s', 'x'));
+        assert!(Layout::Qwerty.is_adjacent('s', 'z'));
+        assert!(!Layout::Qwerty.is_adjacent('s', 'q'));
+        assert!(!Layout::Qwerty.is_adjacent('s', 'c'));
+        assert!(!Layout::Qwerty.is_adjacent('s', 's'));
+        assert!(Layout::Azerty.is_adjacent('a', 'z'));
+        assert!(Layout::Azerty.is_adjacent('q', 'a'));
+        assert!(!Layout::Qwerty.is_adjacent('q', 'z'));
+        assert!(Layout::Dvorak.is_adjacent('h', 't'));
+        assert!(!Layout::Dvorak.is_adjacent('h', 'j'));
+        assert!(!Layout::Qwerty.is_adjacent('a', 'か'));
+    }
+
+    #[test]
+    fn test_is_typo() {
+        let qwerty = Layout::Qwerty;
+        assert!(is_typo(qwerty, "kq", "ka"));
+        assert!(!is_typo(qwerty, "ls", "ka"));
+        assert!(!is_typo(qwerty, "shu", "shi"));
+        assert!(!is_typo(qwerty, "sha", "shi"));
+        assert!(!is_typo(qwerty, "sho", "shi"));
+        assert!(!is_typo(qwerty, "ku", "ki"));
+        assert!(is_typo(qwerty, "sjo", "sho"));
+        assert!(is_typo(qwerty, "hsi", "shi"));
+        assert!(is_typo(qwerty, "sih", "shi"));
+        assert!(is_typo(qwerty, "shii", "shi"));
+        assert!(is_typo(qwerty, "sdhi", "shi"));
+        assert!(!is_typo(qwerty, "spi", "shi"));
+        assert!(!is_typo(qwerty, "shi", "shi"));
+        assert!(!is_typo(qwerty, "s", "shi"));
+        assert!(!is_typo(qwerty, "shpi", "shi"));
+        assert!(is_typo(Layout::Dvorak, "hsu", "tsu"));
+        assert!(!is_typo(Layout::Dvorak, "ta", "ha"));
+        assert!(!is_typo(Layout::Dvorak, "ja", "ha"));
+    }
+}
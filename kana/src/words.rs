@@ -1,22 +1,300 @@
+use regex::Regex;
+
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use dict::Dictionary;
+use list::{parse_list, Entry};
+use romaji::to_romaji;
+use split::{tokenize, SyllableKind};
+use tables::{LongVowels, RomanizationSystem};
+
+/// Rank, word, occurrences, percentage, reading, gloss and part of
+/// speech for an entry in `WORD_LIST`.
+type WordEntry = (
+    u32,
+    &'static str,
+    i32,
+    f64,
+    Option<&'static str>,
+    Option<&'static str>,
+    Option<&'static str>,
+);
+
 // This is generated by `build.rs` and contains:
 //
-//     pub static WORD_LIST: &[(&'static str, i32)] = ...
+//     pub static WORD_LIST: &[WordEntry] = ...
 //
 include!(concat!(env!("OUT_DIR"), "/word_list.rs"));
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Word {
-    pub word: &'static str,
+    pub word: String,
     pub count: i32,
+
+    /// Rank of the word in the list, by frequency.
+    pub rank: u32,
+
+    /// Frequency of the word in the list's corpus, as a percentage.
+    pub percentage: f64,
+
+    /// Reading of the word in kana, if `word` has kanji.
+    pub reading: Option<String>,
+
+    /// English meaning of the word.
+    pub gloss: Option<String>,
+
+    /// Part of speech for the word (e.g. `noun` or `verb`).
+    pub pos: Option<String>,
+
+    /// True for pseudo-words made by `pseudo_word`, which are not
+    /// real words.
+    pub generated: bool,
+}
+
+impl Word {
+    /// Returns the kana for the word: the `reading`, if there is one,
+    /// or else the word itself. This is what is used for training.
+    pub fn kana(&self) -> &str {
+        self.reading.as_ref().unwrap_or(&self.word)
+    }
+
+    /// Returns a pseudo-word made by `pseudo_word`.
+    pub fn generated(word: String) -> Word {
+        Word {
+            word,
+            count: 0,
+            rank: 0,
+            percentage: 0.0,
+            reading: None,
+            gloss: None,
+            pos: None,
+            generated: true,
+        }
+    }
+}
+
+impl From<Entry> for Word {
+    fn from(entry: Entry) -> Word {
+        Word {
+            word: entry.word,
+            count: entry.count,
+            rank: entry.rank,
+            percentage: entry.percentage,
+            reading: entry.reading,
+            gloss: entry.gloss,
+            pos: entry.pos,
+            generated: false,
+        }
+    }
 }
 
 lazy_static! {
     pub static ref WORDS: Vec<Word> = {
         let mut m = Vec::new();
         for it in WORD_LIST.iter().cloned() {
-            let (word, count) = it;
-            m.push(Word { word, count });
+            let (rank, word, count, percentage, reading, gloss, pos) = it;
+            m.push(Word {
+                word: word.to_string(),
+                count,
+                rank,
+                percentage,
+                reading: reading.map(String::from),
+                gloss: gloss.map(String::from),
+                pos: pos.map(String::from),
+                generated: false,
+            });
         }
         m
     };
 }
+
+lazy_static! {
+    pub static ref ALL_WORDS: Vec<Word> =
+        WORDS.iter().filter(|x| is_valid_word(x)).cloned().collect();
+}
+
+lazy_static! {
+    pub static ref WORDS_BY_CHAR: HashMap<char, Vec<usize>> = index_words(&ALL_WORDS);
+}
+
+/// Source for the list of words used to build a `WordSet`.
+pub trait WordSource {
+    /// Returns all the words that can be used for training.
+    fn words(&self) -> &[Word];
+
+    /// Returns the indexes in `words` for the words that contain the
+    /// given character.
+    fn with_char(&self, chr: char) -> &[usize];
+}
+
+/// The word list embedded at compile time from `words.txt`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct EmbeddedWords;
+
+impl WordSource for EmbeddedWords {
+    fn words(&self) -> &[Word] {
+        &ALL_WORDS
+    }
+
+    fn with_char(&self, chr: char) -> &[usize] {
+        WORDS_BY_CHAR.get(&chr).map(|x| x.as_slice()).unwrap_or(&[])
+    }
+}
+
+/// A word list loaded at runtime.
+///
+/// The list uses the same format as `words.txt`: a header line and
+/// then one word per line with the `Rank,Word,Occurrences` columns,
+/// separated by either commas or tabs. The list can also have the
+/// optional `Percentage`, `Reading`, `Gloss` and `POS` columns.
+#[derive(Debug, Clone, Default)]
+pub struct FileWords {
+    words: Vec<Word>,
+    by_char: HashMap<char, Vec<usize>>,
+}
+
+impl FileWords {
+    /// Loads the word list from a CSV or TSV file.
+    pub fn open<P: AsRef<Path>>(path: P) -> io::Result<FileWords> {
+        let text = fs::read_to_string(path)?;
+        FileWords::parse(&text)
+    }
+
+    /// Parses the word list from the text of a CSV or TSV file.
+    ///
+    /// ```
+    /// use kana::{FileWords, WordSource};
+    ///
+    /// let list = FileWords::parse("Rank\tWord\tOccurrences\n1\tすし\t10\n2\tてんぷら\t5\n").unwrap();
+    /// assert_eq!(list.words().len(), 2);
+    /// assert_eq!(list.with_char('す'), &[0]);
+    /// ```
+    pub fn parse(text: &str) -> io::Result<FileWords> {
+        Ok(FileWords::from_words(parse_words(text)?))
+    }
+
+    /// Same as `open`, but uses the dictionary to attach readings to
+    /// the words in kanji (see `Dictionary::annotate`).
+    pub fn open_with_dictionary<P: AsRef<Path>>(
+        path: P,
+        dict: &Dictionary,
+    ) -> io::Result<FileWords> {
+        let text = fs::read_to_string(path)?;
+        Ok(FileWords::from_words(dict.annotate(parse_words(&text)?)))
+    }
+
+    /// Returns a list with the given words, which can be used to
+    /// filter another list (e.g. by part of speech).
+    ///
+    /// Words that cannot be used for training are ignored.
+    pub fn from_words<I: IntoIterator<Item = Word>>(words: I) -> FileWords {
+        let words: Vec<_> = words.into_iter().filter(is_valid_word).collect();
+        let by_char = index_words(&words);
+        FileWords { words, by_char }
+    }
+}
+
+impl WordSource for FileWords {
+    fn words(&self) -> &[Word] {
+        &self.words
+    }
+
+    fn with_char(&self, chr: char) -> &[usize] {
+        self.by_char.get(&chr).map(|x| x.as_slice()).unwrap_or(&[])
+    }
+}
+
+fn parse_words(text: &str) -> io::Result<Vec<Word>> {
+    let words = parse_list(text).map_err(|(line, err)| {
+        let message = format!("{} at line {}", err, line);
+        io::Error::new(io::ErrorKind::InvalidData, message)
+    })?;
+    Ok(words.into_iter().map(Word::from).collect())
+}
+
+fn index_words(words: &[Word]) -> HashMap<char, Vec<usize>> {
+    let mut m = HashMap::new();
+    for (i, it) in words.iter().enumerate() {
+        for chr in it.kana().chars() {
+            let entry = m.entry(chr).or_insert(Vec::new());
+            entry.push(i);
+        }
+    }
+    m
+}
+
+fn is_valid_word(w: &Word) -> bool {
+    lazy_static! {
+        static ref RE: Regex = Regex::new(r"^[-'a-zA-Z0-9]+$").unwrap();
+    }
+    // Invalid kana sequences are spelled as typed in an IME (e.g.
+    // `xtsu`), which would otherwise pass the check below.
+    let syllables = tokenize(
+        w.kana(),
+        RomanizationSystem::default(),
+        LongVowels::default(),
+    );
+    if syllables
+        .iter()
+        .any(|x| matches!(x.kind, SyllableKind::Error(_)))
+    {
+        return false;
+    }
+    let romaji = to_romaji(
+        w.kana(),
+        RomanizationSystem::default(),
+        LongVowels::default(),
+    );
+    RE.is_match(&romaji)
+}
+
+// spell-checker: disable
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_embedded_words() {
+        let word = &WORDS[0];
+        assert_eq!(word.word, "する");
+        assert_eq!(word.rank, 1);
+        assert_eq!(word.count, 138279);
+        assert_eq!(word.percentage, 7.34511);
+    }
+
+    #[test]
+    fn test_file_words() {
+        let text = "Rank,Word,Occurrences\n1,すし,10\n\n2, ラーメン ,5\n3,漢字,3\n";
+        let list = FileWords::parse(text).unwrap();
+        let words: Vec<_> = list
+            .words()
+            .iter()
+            .map(|x| (x.word.as_str(), x.count))
+            .collect();
+        assert_eq!(words, vec![("すし", 10), ("ラーメン", 5)]);
+        assert_eq!(list.with_char('ー'), &[1]);
+        assert_eq!(list.with_char('か'), &[] as &[usize]);
+
+        let text = "Rank,Word,Occurrences\n1,そっ,10\n2,ゃあ,5\n3,そう,3\n";
+        let list = FileWords::parse(text).unwrap();
+        assert_eq!(list.words().len(), 1);
+        assert_eq!(list.words()[0].word, "そう");
+        assert!(ALL_WORDS.iter().all(|x| x.word != "そっ"));
+
+        let text = "Rank\tWord\tOccurrences\tPercentage\n1\tすし\t10\t1%\n";
+        assert_eq!(FileWords::parse(text).unwrap().words().len(), 1);
+    }
+
+    #[test]
+    fn test_file_words_invalid() {
+        let err = FileWords::parse("Rank,Word,Occurrences\n1,すし,10\n2,すし\n").unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        assert_eq!(err.to_string(), "invalid occurrences at line 3");
+    }
+}
@@ -1,34 +1,38 @@
-use regex::Regex;
+use serde::{Deserialize, Serialize};
 
-use std::collections::HashMap;
+use std::collections::BTreeSet;
 use std::collections::HashSet;
+use std::error;
+use std::fmt;
+use std::str::FromStr;
 
-use rand::{seq::SliceRandom, thread_rng};
+use rand::{seq::SliceRandom, Rng, SeedableRng};
+use rand_hc::Hc128Rng;
 
 #[derive(Debug, Clone, Default)]
 pub struct WordSet {
-    pub words: Vec<&'static Word>,
+    pub words: Vec<Word>,
     pub chars: usize,
     pub missing: Vec<char>,
+
+    /// Characters outside the charset that were included by the
+    /// fallback in `build_strict_set`. Always empty for `build_set`.
+    pub extra: Vec<char>,
 }
 
 impl WordSet {
     /// Shuffles all `words` in the WordSet.
-    pub fn shuffle(&mut self) {
-        let mut rng = thread_rng();
-        self.words.as_mut_slice().shuffle(&mut rng);
+    pub fn shuffle<R: Rng>(&mut self, rng: &mut R) {
+        self.words.as_mut_slice().shuffle(rng);
     }
 
     /// Swaps the word at the given index with a random word at a
     /// higher index.
     ///
     /// Returns the swapped index.
-    pub fn swap_current(&mut self, index: usize) -> usize {
-        use rand::Rng;
-
+    pub fn swap_current<R: Rng>(&mut self, rng: &mut R, index: usize) -> usize {
         let size = self.words.len();
         if index < size - 1 {
-            let mut rng = thread_rng();
             let next_index: usize = rng.gen_range(index + 1, size);
             self.words.swap(index, next_index);
             next_index
@@ -36,81 +40,457 @@ impl WordSet {
             index
         }
     }
-}
 
-macro_rules! set_rare {
-    () => {
-        "ぺぢヌヅを"
-    };
+    /// Adds a pseudo-word for each `missing` character that is kana,
+    /// using the characters in `charset` as filler. Those characters
+    /// are removed from `missing`.
+    ///
+    /// The pseudo-words are appended to `words` and flagged with
+    /// `Word::generated`.
+    pub fn fill_missing<R: Rng>(&mut self, rng: &mut R, charset: &str) {
+        let mut missing = Vec::new();
+        for &chr in &self.missing {
+            match pseudo_word(rng, chr, charset) {
+                Some(word) => {
+                    self.chars += word.chars().count();
+                    self.words.push(Word::generated(word));
+                }
+                None => missing.push(chr),
+            }
+        }
+        self.missing = missing;
+    }
 }
 
-macro_rules! set_hiragana {
-    () => ("あいうえおかきくけこがぎぐげごさしすせそざじずぜぞたちつてとだづでどなにぬねのはひふへほばびぶべぼぱぴぷぽまみむめもやゆよらりるれろわん")
+use pseudo::pseudo_word;
+use tables::{gojuon, Category, Column, Kana, Row, Script, Voicing, TABLE};
+use words::{EmbeddedWords, Word, WordSource};
+
+/// Characters that are rare in the words list and are only included
+/// in `SET_ALL_RARE`.
+const SET_RARE: &str = "ぺぢをヅ";
+
+/// Selection of entries from the gojūon table, used to build the
+/// character set for `build_set`.
+///
+/// Each field restricts the selected entries to the given values.
+/// An empty field does not restrict anything. For example, the `ka`
+/// and `sa` rows in katakana only, without the voiced variants:
+///
+/// ```
+/// use kana::{build_set, Category, Charset, EmbeddedWords, Row, Script};
+///
+/// let charset = Charset {
+///     scripts: vec![Script::Katakana],
+///     rows: vec![Row::Ka, Row::Sa],
+///     categories: vec![Category::Basic],
+///     ..Default::default()
+/// };
+/// assert_eq!(charset.chars(), "カキクケコサシスセソ");
+///
+/// let set = build_set(&EmbeddedWords, &mut rand::thread_rng(), &charset.chars(), 0);
+/// assert!(set.missing.is_empty());
+/// ```
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Charset {
+    pub scripts: Vec<Script>,
+    pub rows: Vec<Row>,
+    pub columns: Vec<Column>,
+    pub categories: Vec<Category>,
 }
 
-macro_rules! set_katakana {
-    () => ("アイウエオカキクケコガギグゲゴサシスセソザジズゼゾタチツテトダヂデドナニヌネノハヒフヘホバビブベボパピプペポマミムメモヤユヨラリルレロワヲン")
+impl Default for Charset {
+    /// The basic characters from all rows and columns in both
+    /// scripts: the gojūon table with the voiced and semi-voiced
+    /// variants.
+    fn default() -> Charset {
+        Charset {
+            scripts: Vec::new(),
+            rows: Vec::new(),
+            columns: Vec::new(),
+            categories: vec![Category::Basic, Category::Voiced, Category::SemiVoiced],
+        }
+    }
 }
 
-pub const SET_HIRAGANA: &'static str = set_hiragana!();
-pub const SET_KATAKANA: &'static str = set_katakana!();
-pub const SET_ALL: &'static str = concat!(set_hiragana!(), set_katakana!());
-pub const SET_ALL_RARE: &'static str = concat!(set_hiragana!(), set_katakana!(), set_rare!());
+impl Charset {
+    /// Returns the characters for the selected entries, in table
+    /// order and grouped by script.
+    ///
+    /// Yōon entries contribute both of their characters (e.g. `き`
+    /// and `ゃ` for `きゃ`). Each character is included only once.
+    pub fn chars(&self) -> String {
+        fn any<T: PartialEq>(list: &[T], value: &T) -> bool {
+            list.is_empty() || list.contains(value)
+        }
 
-use romaji::to_romaji;
-use words::{Word, WORDS};
+        let scripts = if self.scripts.is_empty() {
+            &[Script::Hiragana, Script::Katakana][..]
+        } else {
+            &self.scripts[..]
+        };
 
-lazy_static! {
-    pub static ref ALL_WORDS: Vec<&'static Word> = {
-        let mut m = Vec::new();
-        for it in WORDS.iter() {
-            if is_valid_word(it) {
-                m.push(it);
+        let mut out = String::new();
+        for &script in scripts {
+            let entries = gojuon().iter().filter(|x| {
+                any(&self.rows, &x.row)
+                    && x.column
+                        .map(|c| any(&self.columns, &c))
+                        .unwrap_or(self.columns.is_empty())
+                    && any(&self.categories, &x.category)
+            });
+            for chr in entries.flat_map(|x| x.kana(script).chars().collect::<Vec<_>>()) {
+                if !out.contains(chr) {
+                    out.push(chr);
+                }
             }
         }
-        m
+        out
+    }
+}
+
+/// Returns the basic characters for the script: all the gojūon
+/// table with the voiced and semi-voiced variants, except for the
+/// small, obsolete and rare characters.
+fn script_set(script: Script) -> String {
+    TABLE
+        .iter()
+        .filter(|x| x.script() == script && !x.is_small() && is_basic(x))
+        .map(|x| x.get_char())
+        .filter(|&x| !SET_RARE.contains(x))
+        .collect()
+}
+
+// True for the characters in the modern gojūon table and their
+// voiced variants (e.g. excludes `ゐ` and `ヴ`).
+fn is_basic(kana: &Kana) -> bool {
+    let base = kana.without_dakuten();
+    let obsolete = "ゐゑヰヱ".contains(base.get_char());
+    let voiced = match kana.voicing() {
+        Voicing::Unvoiced => true,
+        Voicing::Voiced => matches!(
+            base.row(),
+            Some(Row::Ka) | Some(Row::Sa) | Some(Row::Ta) | Some(Row::Ha)
+        ),
+        Voicing::SemiVoiced => true,
     };
+    kana.row().is_some() && !obsolete && voiced
 }
 
 lazy_static! {
-    pub static ref WORDS_BY_CHAR: HashMap<char, Vec<usize>> = {
-        let mut m = HashMap::new();
-        for (i, it) in ALL_WORDS.iter().enumerate() {
-            for chr in it.word.chars() {
-                let mut entry = m.entry(chr).or_insert(Vec::new());
-                entry.push(i);
+    pub static ref SET_HIRAGANA: String = script_set(Script::Hiragana);
+    pub static ref SET_KATAKANA: String = script_set(Script::Katakana);
+    pub static ref SET_ALL: String = format!("{}{}", *SET_HIRAGANA, *SET_KATAKANA);
+    pub static ref SET_ALL_RARE: String = format!("{}{}", *SET_ALL, SET_RARE);
+}
+
+/// Builds a random set of words from `source` covering the characters
+/// in `charset`, with at least `hint_len` characters in total if
+/// possible (zero for no minimum).
+///
+/// Use `Charset::chars` to build `charset` from the gojūon table.
+///
+/// The set depends only on the `rng` state, so a seeded generator
+/// always builds the same set (see `SetCode`).
+pub fn build_set<S, R>(source: &S, rng: &mut R, charset: &str, hint_len: usize) -> WordSet
+where
+    S: WordSource + ?Sized,
+    R: Rng,
+{
+    build(source, rng, charset, None, hint_len)
+}
+
+/// Same as `build_set`, but only chooses words made entirely of the
+/// characters in `charset` and `allowed`. The characters in `allowed`
+/// are not required to be in the set (e.g. `ー` and `っ`).
+///
+/// If no word can be made of those characters for some character in
+/// `charset`, then the word with the least amount of other characters
+/// is used instead, and those characters are reported in `extra`.
+///
+/// ```
+/// use kana::{build_strict_set, EmbeddedWords};
+///
+/// let mut rng = rand::thread_rng();
+/// let set = build_strict_set(&EmbeddedWords, &mut rng, "あいうえおかきくけこ", "ー", 0);
+/// for it in set.words {
+///     assert!(it.kana().chars().all(|x| "あいうえおかきくけこー".contains(x)));
+/// }
+/// ```
+pub fn build_strict_set<S, R>(
+    source: &S,
+    rng: &mut R,
+    charset: &str,
+    allowed: &str,
+    hint_len: usize,
+) -> WordSet
+where
+    S: WordSource + ?Sized,
+    R: Rng,
+{
+    let allowed = charset.chars().chain(allowed.chars()).collect();
+    build(source, rng, charset, Some(&allowed), hint_len)
+}
+
+/// Builds a set of pseudo-words only, for pure character drills.
+///
+/// There is one pseudo-word for each character in `charset`, which
+/// is repeated until the set has at least `hint_len` characters.
+pub fn build_pseudo_set<R: Rng>(rng: &mut R, charset: &str, hint_len: usize) -> WordSet {
+    let mut set = WordSet {
+        missing: charset.chars().collect(),
+        ..Default::default()
+    };
+    set.fill_missing(rng, charset);
+
+    let missing = set.missing.clone();
+    while set.chars < hint_len && !set.words.is_empty() {
+        set.missing = charset.chars().filter(|x| !missing.contains(x)).collect();
+        set.fill_missing(rng, charset);
+    }
+    set.missing = missing;
+    set
+}
+
+/// Compact code for a word set, which can be shared to rebuild
+/// exactly the same set with `SetCode::build`.
+///
+/// The code encodes the random seed, the charset and the requested
+/// length. Sets are only the same for the same word list.
+///
+/// ```
+/// use kana::SetCode;
+///
+/// let code = SetCode::new("かきくけこ", 50);
+/// let text = code.to_string();
+/// let same: SetCode = text.parse().unwrap();
+/// assert_eq!(same, code);
+/// assert_eq!(same.build().words, code.build().words);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SetCode {
+    pub seed: u64,
+    pub charset: String,
+    pub size: usize,
+}
+
+/// Version of the `SetCode` encoding.
+const SET_CODE_VERSION: u8 = 1;
+
+/// Kana for each bit of the charset mask in a `SetCode`. This must
+/// not depend on `TABLE`, so new kana are only ever appended here.
+const SET_CODE_KANA: &str = "\
+ッャュョァィゥェォヮヴーヽヾアイウエオカキクケコガギグゲゴサシスセソザジズゼゾタチツテトダヂヅデドナニヌネノハヒフヘホバビブベボパピプペポマミムメモヤユヨラリルレロワヲンヰヱヷヸヹ\
+ヺヵヶっゃゅょぁぃぅぇぉゎゔゝゞあいうえおかきくけこがぎぐげごさしすせそざじずぜぞたちつてとだぢづでどなにぬねのはひふへほばびぶべぼぱぴぷぺぽまみむめもやゆよらりるれろわをんゐゑゕゖ";
+
+/// Alphabet for the `SetCode` text encoding (URL-safe base64).
+const SET_CODE_ALPHABET: &[u8] =
+    b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
+
+impl SetCode {
+    /// Returns a code with a random seed for the given charset and
+    /// length (see `build_set`).
+    ///
+    /// Characters in `charset` that are not kana are ignored. The size
+    /// is clamped to `u16::MAX`, the largest that can be encoded.
+    pub fn new(charset: &str, size: usize) -> SetCode {
+        SetCode::with_seed(rand::random(), charset, size)
+    }
+
+    /// Same as `new`, but with the given seed.
+    pub fn with_seed(seed: u64, charset: &str, size: usize) -> SetCode {
+        // Only the kana characters can be encoded, and we keep them
+        // in the mask order so that decoding gives the same charset.
+        let charset = SET_CODE_KANA
+            .chars()
+            .filter(|&x| charset.contains(x))
+            .collect();
+        SetCode {
+            seed,
+            charset,
+            size: size.min(u16::MAX as usize),
+        }
+    }
+
+    /// Returns the seeded random generator for the code.
+    ///
+    /// This is HC-128 and not `StdRng`, which may change between
+    /// versions of `rand` and would change the set for shared codes.
+    pub fn rng(&self) -> Hc128Rng {
+        Hc128Rng::seed_from_u64(self.seed)
+    }
+
+    /// Builds the set for the code from the embedded word list.
+    pub fn build(&self) -> WordSet {
+        self.build_from(&EmbeddedWords)
+    }
+
+    /// Builds the set for the code using `build_set` and fills any
+    /// missing characters with `WordSet::fill_missing`. The words
+    /// are shuffled.
+    pub fn build_from<S: WordSource + ?Sized>(&self, source: &S) -> WordSet {
+        let mut rng = self.rng();
+        let mut set = build_set(source, &mut rng, &self.charset, self.size);
+        set.fill_missing(&mut rng, &self.charset);
+        set.shuffle(&mut rng);
+        set
+    }
+}
+
+impl fmt::Display for SetCode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut bytes = vec![SET_CODE_VERSION];
+        bytes.extend((0..8).map(|i| (self.seed >> (i * 8)) as u8));
+        let size = self.size.min(u16::MAX as usize);
+        bytes.extend(&[size as u8, (size >> 8) as u8]);
+
+        // The charset is a bit mask over the kana in the table.
+        let mut mask = vec![0u8; SET_CODE_KANA.chars().count().div_ceil(8)];
+        for (index, kana) in SET_CODE_KANA.chars().enumerate() {
+            if self.charset.contains(kana) {
+                mask[index / 8] |= 1 << (index % 8);
             }
         }
-        m
-    };
+        while mask.last() == Some(&0) {
+            mask.pop();
+        }
+        bytes.extend(mask);
+
+        // Encode as base64, without padding.
+        for chunk in bytes.chunks(3) {
+            let value = chunk
+                .iter()
+                .enumerate()
+                .fold(0u32, |acc, (i, &x)| acc | (x as u32) << (16 - i * 8));
+            for i in 0..=chunk.len() {
+                let index = (value >> (18 - i * 6)) & 0x3F;
+                write!(f, "{}", SET_CODE_ALPHABET[index as usize] as char)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for SetCode {
+    type Err = InvalidSetCode;
+
+    fn from_str(code: &str) -> Result<SetCode, InvalidSetCode> {
+        let mut bytes = Vec::new();
+        let mut value = 0u32;
+        let mut bits = 0;
+        for chr in code.trim().bytes() {
+            let digit = SET_CODE_ALPHABET
+                .iter()
+                .position(|&x| x == chr)
+                .ok_or(InvalidSetCode)?;
+            value = (value << 6) | digit as u32;
+            bits += 6;
+            if bits >= 8 {
+                bits -= 8;
+                bytes.push((value >> bits) as u8);
+                value &= (1 << bits) - 1;
+            }
+        }
+
+        if bytes.len() < 11 || bytes[0] != SET_CODE_VERSION {
+            return Err(InvalidSetCode);
+        }
+
+        let seed = (0..8).fold(0u64, |acc, i| acc | (bytes[1 + i] as u64) << (i * 8));
+        let size = bytes[9] as usize | (bytes[10] as usize) << 8;
+        let mask = &bytes[11..];
+        if mask.len() > SET_CODE_KANA.chars().count().div_ceil(8) {
+            return Err(InvalidSetCode);
+        }
+
+        let charset = SET_CODE_KANA
+            .chars()
+            .enumerate()
+            .filter(|(index, _)| {
+                mask.get(index / 8)
+                    .is_some_and(|x| x & (1 << (index % 8)) != 0)
+            })
+            .map(|(_, kana)| kana)
+            .collect();
+        Ok(SetCode {
+            seed,
+            charset,
+            size,
+        })
+    }
 }
 
-pub fn build_set(charset: &str, hint_len: usize) -> WordSet {
-    // Build a set with all the required characters.
-    let mut required = HashSet::new();
+/// Error returned when parsing an invalid `SetCode`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InvalidSetCode;
+
+impl fmt::Display for InvalidSetCode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "invalid set code")
+    }
+}
+
+impl error::Error for InvalidSetCode {}
+
+fn build<S, R>(
+    source: &S,
+    rng: &mut R,
+    charset: &str,
+    allowed: Option<&HashSet<char>>,
+    hint_len: usize,
+) -> WordSet
+where
+    S: WordSource + ?Sized,
+    R: Rng,
+{
+    let all_words = source.words();
+
+    // Build a set with all the required characters. This is ordered
+    // so that the set only depends on the `rng`.
+    let mut required = BTreeSet::new();
     for chr in charset.chars() {
         required.insert(chr);
     }
 
-    // Indexes in ALL_WORDS for the words in the set we are building.
+    // Indexes in `all_words` for the words in the set we are building.
     let mut set_indexes: HashSet<usize> = HashSet::new();
 
     // Characters that are not found.
     let mut missing: HashSet<char> = HashSet::new();
 
-    let mut chars = 0;
+    // Characters outside the allowed set from fallback words.
+    let mut extra: HashSet<char> = HashSet::new();
+
+    // Number of characters in a word that are not allowed.
+    let outside = |index: usize| match allowed {
+        Some(allowed) => all_words[index]
+            .kana()
+            .chars()
+            .filter(|x| !allowed.contains(x))
+            .count(),
+        None => 0,
+    };
 
-    let mut rng = thread_rng();
+    let mut chars = 0;
 
-    fn choose_index(set: &HashSet<usize>, input: &Vec<usize>) -> Option<usize> {
-        let mut rng = thread_rng();
+    fn choose_index<R, F>(
+        rng: &mut R,
+        all_words: &[Word],
+        set: &HashSet<usize>,
+        input: &[usize],
+        keep: F,
+    ) -> Option<usize>
+    where
+        R: Rng,
+        F: Fn(usize) -> bool,
+    {
         let mut indexes: Vec<usize> = Vec::new();
         for index in input {
-            if !set.contains(index) {
+            if !set.contains(index) && keep(*index) {
                 indexes.push(*index);
             }
         }
-        if let Ok(value) = indexes.choose_weighted(&mut rng, |&idx| ALL_WORDS[idx].count) {
+        if let Ok(value) = indexes.choose_weighted(rng, |&idx| all_words[idx].count) {
             Some(*value)
         } else {
             None
@@ -118,26 +498,42 @@ pub fn build_set(charset: &str, hint_len: usize) -> WordSet {
     }
 
     // Add words to the set for each character in required.
-    while required.len() > 0 && (hint_len == 0 || chars < hint_len) {
+    while !required.is_empty() && (hint_len == 0 || chars < hint_len) {
         // We choose one character at random to start so as to not
         // bias the resulting set.
         let vec: Vec<_> = required.iter().cloned().collect();
-        let elem = vec.choose(&mut rng).unwrap();
+        let elem = vec.choose(rng).unwrap();
         required.remove(elem);
 
         // Choose one of the words that contains the given character.
         let mut ok = false;
-        if let Some(word_indexes) = WORDS_BY_CHAR.get(elem) {
-            if let Some(index) = choose_index(&set_indexes, word_indexes) {
+        let word_indexes = source.with_char(*elem);
+        if !word_indexes.is_empty() {
+            let mut index = choose_index(rng, all_words, &set_indexes, word_indexes, |x| {
+                outside(x) == 0
+            });
+            if index.is_none() {
+                // Fallback to the words closest to the allowed set.
+                let unused = word_indexes.iter().filter(|x| !set_indexes.contains(x));
+                if let Some(min) = unused.map(|&x| outside(x)).min() {
+                    index = choose_index(rng, all_words, &set_indexes, word_indexes, |x| {
+                        outside(x) == min
+                    });
+                }
+            }
+            if let Some(index) = index {
                 // Add the word to the set.
                 set_indexes.insert(index);
                 ok = true;
 
                 // Remove any character of this word from the required
                 // set.
-                for chr in ALL_WORDS[index].word.chars() {
+                for chr in all_words[index].kana().chars() {
                     chars += 1;
                     required.remove(&chr);
+                    if allowed.is_some_and(|x| !x.contains(&chr)) {
+                        extra.insert(chr);
+                    }
                 }
             }
         }
@@ -157,16 +553,19 @@ pub fn build_set(charset: &str, hint_len: usize) -> WordSet {
     'outer: while chars < hint_len && changed {
         {
             changed = false;
-            letters.as_mut_slice().shuffle(&mut rng);
+            letters.as_mut_slice().shuffle(rng);
         }
         for chr in &letters {
-            if let Some(word_indexes) = WORDS_BY_CHAR.get(&chr) {
-                if let Some(index) = choose_index(&set_indexes, word_indexes) {
+            let word_indexes = source.with_char(*chr);
+            if !word_indexes.is_empty() {
+                if let Some(index) = choose_index(rng, all_words, &set_indexes, word_indexes, |x| {
+                    outside(x) == 0
+                }) {
                     // Add the word to the set.
                     set_indexes.insert(index);
                     changed = true;
 
-                    chars += ALL_WORDS[index].word.chars().count();
+                    chars += all_words[index].kana().chars().count();
                     if chars >= hint_len {
                         break 'outer;
                     }
@@ -180,24 +579,94 @@ pub fn build_set(charset: &str, hint_len: usize) -> WordSet {
 
     let mut words = Vec::new();
     for it in indexes {
-        let row = ALL_WORDS[*it];
-        words.push(row);
+        let row = &all_words[*it];
+        words.push(row.clone());
     }
 
     let mut missing: Vec<_> = missing.iter().cloned().collect();
     missing.sort();
 
+    let mut extra: Vec<_> = extra.into_iter().collect();
+    extra.sort();
+
     WordSet {
         words,
         chars,
         missing,
+        extra,
     }
 }
 
-fn is_valid_word(w: &Word) -> bool {
-    lazy_static! {
-        static ref RE: Regex = Regex::new(r"^[-a-zA-Z0-9]+$").unwrap();
+// spell-checker: disable
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::StdRng;
+    use words::FileWords;
+
+    fn words(set: &WordSet) -> Vec<&str> {
+        set.words.iter().map(|x| x.word.as_str()).collect()
+    }
+
+    #[test]
+    fn test_build_strict_set() {
+        let text = "Rank,Word,Occurrences\n1,すしや,20\n2,すし,10\n3,かし,5\n";
+        let list = FileWords::parse(text).unwrap();
+        for seed in 0..10 {
+            let mut rng = StdRng::seed_from_u64(seed);
+            let set = build_strict_set(&list, &mut rng, "すし", "", 0);
+            assert_eq!(words(&set), vec!["すし"]);
+            assert!(set.missing.is_empty());
+            assert!(set.extra.is_empty());
+        }
+    }
+
+    #[test]
+    fn test_build_strict_set_closest_word() {
+        // No word has only `す`, so the word with the least amount of
+        // other characters is used, even if it is less common.
+        let text = "Rank,Word,Occurrences\n1,すしや,20\n2,すし,10\n3,かし,5\n";
+        let list = FileWords::parse(text).unwrap();
+        for seed in 0..10 {
+            let mut rng = StdRng::seed_from_u64(seed);
+            let set = build_strict_set(&list, &mut rng, "すか", "ー", 0);
+            assert_eq!(words(&set), vec!["すし", "かし"]);
+            assert!(set.missing.is_empty());
+            assert_eq!(set.extra, vec!['し']);
+
+            let mut rng = StdRng::seed_from_u64(seed);
+            let set = build_strict_set(&list, &mut rng, "すかぬ", "", 0);
+            assert_eq!(set.missing, vec!['ぬ']);
+
+            let mut rng = StdRng::seed_from_u64(seed);
+            let set = build_set(&list, &mut rng, "すか", 0);
+            assert!(set.extra.is_empty());
+        }
+    }
+
+    #[test]
+    fn test_set_code_stable() {
+        // Shared codes must always decode to the same set.
+        let code: SetCode = "ASoAAAAAAAAAMgAAAAABAAAAAAAAAAAAgA8".parse().unwrap();
+        assert_eq!(code, SetCode::with_seed(42, "かきくけこガ", 50));
+        assert_eq!(code.charset, "ガかきくけこ");
+        assert_eq!(code.rng().gen::<u32>(), 1092445067);
+
+        let kana: Vec<char> = SET_CODE_KANA.chars().collect();
+        for it in TABLE {
+            let count = kana.iter().filter(|&&x| x == it.get_char()).count();
+            assert_eq!(count, 1, "{}", it.get_char());
+        }
+        assert_eq!(kana.len(), TABLE.len());
+    }
+
+    #[test]
+    fn test_set_code_size() {
+        for &(size, expected) in &[(0, 0), (65535, 65535), (65536, 65535), (70000, 65535)] {
+            let code = SetCode::with_seed(1, "かき", size);
+            assert_eq!(code.size, expected);
+            assert_eq!(code.to_string().parse(), Ok(code));
+        }
     }
-    let romaji = to_romaji(w.word);
-    return RE.is_match(&romaji);
 }
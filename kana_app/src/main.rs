@@ -1,6 +1,7 @@
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
 extern crate kana;
+extern crate rand;
 extern crate regex;
 extern crate web_view;
 
@@ -46,6 +47,12 @@ enum Message {
     /// Start a new training session.
     Start { set: Set, size: usize },
 
+    /// Set the options used to detect typos.
+    Options {
+        layout: kana::Layout,
+        forgive_typos: bool,
+    },
+
     /// Submit an answer to the training session.
     Submit { text: String, elapsed_ms: u64 },
 
@@ -82,7 +89,27 @@ fn main() {
     println!("\nInternal server started at {}\n", url,);
 
     let mut log_counter: u64 = 1;
-    let model = Model::new();
+    let mut model = Model::new();
+
+    // An optional word list can be given in the command line, in the
+    // same format as `words.txt`, and an optional EDICT or JMdict
+    // dictionary to train with the words in kanji and show their
+    // meaning.
+    match load_words() {
+        Ok((words, dict)) => {
+            if let Some(words) = words {
+                model.set_words(words);
+            }
+            if let Some(dict) = dict {
+                model.set_dictionary(dict);
+            }
+        }
+        Err(err) => {
+            eprintln!("\n{}\n", err);
+            std::process::exit(1);
+        }
+    }
+
     web_view::builder()
         .title("Kana")
         .content(web_view::Content::Url(url))
@@ -119,6 +146,13 @@ fn main() {
                         update(webview, |model| model.start(set, size));
                     }
 
+                    Message::Options {
+                        layout,
+                        forgive_typos,
+                    } => {
+                        update(webview, |model| model.set_options(layout, forgive_typos));
+                    }
+
                     Message::Submit { text, elapsed_ms } => {
                         update(webview, |model| model.submit(&text, elapsed_ms));
                     }
@@ -159,6 +193,46 @@ fn main() {
     server.stop();
 }
 
+/// Loads the word list and dictionary from the command line arguments:
+///
+///     kana [WORDS] [--dict EDICT|JMDICT]
+///
+/// The word list is `None` if the embedded list should be used.
+fn load_words() -> std::result::Result<(Option<kana::FileWords>, Option<kana::Dictionary>), String>
+{
+    let mut path = None;
+    let mut dict_path = None;
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        if arg == "--dict" {
+            dict_path = Some(args.next().ok_or("missing dictionary file for --dict")?);
+        } else {
+            path = Some(arg);
+        }
+    }
+
+    let dict = match &dict_path {
+        Some(file) => {
+            let dict = kana::Dictionary::open(file);
+            Some(dict.map_err(|err| format!("Error loading {}: {}", file, err))?)
+        }
+        None => None,
+    };
+
+    let error = |file: &str, err: std::io::Error| format!("Error loading {}: {}", file, err);
+    let words = match (&path, &dict) {
+        (Some(file), Some(dict)) => {
+            kana::FileWords::open_with_dictionary(file, dict).map_err(|err| error(file, err))?
+        }
+        (Some(file), None) => kana::FileWords::open(file).map_err(|err| error(file, err))?,
+        (None, Some(dict)) => {
+            kana::FileWords::from_words(dict.annotate(kana::WORDS.iter().cloned()))
+        }
+        (None, None) => return Ok((None, None)),
+    };
+    Ok((Some(words), dict))
+}
+
 /// Send a command to the JavaScript application running in the web
 /// view.
 fn send_command<T>(webview: &mut web_view::WebView<T>, cmd: Command) {
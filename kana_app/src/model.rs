@@ -1,9 +1,10 @@
 use std::collections::HashMap;
+use std::sync::Arc;
 
 use serde::{Deserialize, Serialize};
 
 /// Sets of characters for training.
-#[derive(Serialize, Deserialize, Debug, Clone, Copy)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub enum Set {
     /// Hiragana only.
     Hiragana,
@@ -13,6 +14,46 @@ pub enum Set {
     All,
     /// Hiragana + Katakana + Rare
     Rare,
+    /// Characters chosen by the user.
+    Custom(Vec<char>),
+    /// Same set as a previous session, from its `kana::SetCode`.
+    Code(String),
+}
+
+impl Set {
+    /// Returns the code to build the set with the given size, and the
+    /// requested characters that are not valid for training (i.e. not
+    /// kana).
+    ///
+    /// For `Set::Code` the size is given by the code.
+    fn code(&self, size: usize) -> Result<(kana::SetCode, Vec<char>), kana::InvalidSetCode> {
+        let (charset, invalid) = match self {
+            Set::Hiragana => (kana::SET_HIRAGANA.clone(), Vec::new()),
+            Set::Katakana => (kana::SET_KATAKANA.clone(), Vec::new()),
+            Set::All => (kana::SET_ALL.clone(), Vec::new()),
+            Set::Rare => (kana::SET_ALL_RARE.clone(), Vec::new()),
+            Set::Code(code) => return Ok((code.parse()?, Vec::new())),
+            Set::Custom(chars) => {
+                let text: String = chars.iter().collect();
+                let text = kana::normalize(&text).text;
+
+                let mut charset = String::new();
+                let mut invalid = Vec::new();
+                for chr in text.chars().filter(|x| !x.is_whitespace()) {
+                    if charset.contains(chr) || invalid.contains(&chr) {
+                        continue;
+                    }
+                    if kana::Kana::get(chr).is_some() {
+                        charset.push(chr);
+                    } else {
+                        invalid.push(chr);
+                    }
+                }
+                (charset, invalid)
+            }
+        };
+        Ok((kana::SetCode::new(&charset, size), invalid))
+    }
 }
 
 // Pages for the application.
@@ -29,15 +70,40 @@ pub struct Model {
     /// Current set selected.
     set: Set,
 
+    /// Keyboard layout used to tell typos from recognition errors.
+    layout: kana::Layout,
+
+    /// If true, answers where all the mistakes are typos are accepted.
+    forgive_typos: bool,
+
     /// Current page for the application.
     page: Page,
 
-    /// Current word for the training set.
+    /// Code to share the current training set.
+    code: String,
+
+    /// Error message for the start page.
+    error: Option<String>,
+
+    /// Current word for the training set, in kana.
     word: String,
 
-    /// Missing characters from the generated set.
+    /// Current word in kanji, if `word` is its reading.
+    kanji: Option<String>,
+
+    /// True if the current word is a pseudo-word generated to cover
+    /// a character that no dictionary word has.
+    generated: bool,
+
+    /// Requested characters that are missing from the generated set
+    /// because no word, not even a pseudo-word, could be made with
+    /// them.
     missing: Vec<char>,
 
+    /// Requested characters that are not valid for training (i.e. not
+    /// kana), from a custom set.
+    invalid: Vec<char>,
+
     /// Number of hits for current training session.
     hits: usize,
 
@@ -59,6 +125,13 @@ pub struct Model {
     /// Match for the last submitted kana.
     submitted: Option<kana::Match>,
 
+    /// Last word answered correctly, to show its meaning.
+    answered: Option<kana::Word>,
+
+    /// English meanings for the last submitted word, either from the
+    /// word list or from the dictionary.
+    glosses: Vec<String>,
+
     /// Set of words for the current training.
     #[serde(skip)]
     word_set: kana::WordSet,
@@ -70,12 +143,23 @@ pub struct Model {
     /// Errors by kana character.
     errors: HashMap<char, usize>,
 
+    /// Typos by kana character. Those are not counted in `errors`.
+    typos: HashMap<char, usize>,
+
     /// Average time spent per char.
     times: Vec<CharAverage>,
 
     /// Time spent for each word in the set.
     #[serde(skip)]
     word_time: Vec<u64>,
+
+    /// Word list loaded at startup, instead of the embedded one.
+    #[serde(skip)]
+    words: Option<Arc<kana::FileWords>>,
+
+    /// Dictionary used to look up the meaning of each word.
+    #[serde(skip)]
+    dict: Option<Arc<kana::Dictionary>>,
 }
 
 /// Report the average time spent for a kana char.
@@ -90,10 +174,17 @@ impl Model {
     pub fn new() -> Model {
         return Model {
             set: Set::All,
+            layout: Default::default(),
+            forgive_typos: false,
             page: Page::Start,
+            code: String::new(),
+            error: None,
 
             word: String::new(),
+            kanji: None,
+            generated: false,
             missing: Vec::new(),
+            invalid: Vec::new(),
 
             hits: 0,
             misses: 0,
@@ -102,45 +193,82 @@ impl Model {
             chars_total: 0,
             answer_time: 0,
             submitted: None,
+            answered: None,
+            glosses: Vec::new(),
 
             word_set: Default::default(),
             word_index: 0,
 
             errors: HashMap::new(),
+            typos: HashMap::new(),
             times: Vec::new(),
             word_time: Vec::new(),
+            words: None,
+            dict: None,
         };
     }
 
+    /// Uses the given word list for the training sessions.
+    pub fn set_words(&mut self, words: kana::FileWords) {
+        self.words = Some(Arc::new(words));
+    }
+
+    /// Uses the given dictionary to show the meaning of the words
+    /// after each answer.
+    pub fn set_dictionary(&mut self, dict: kana::Dictionary) {
+        self.dict = Some(Arc::new(dict));
+    }
+
+    /// Sets the options used to detect typos.
+    pub fn set_options(&mut self, layout: kana::Layout, forgive_typos: bool) {
+        self.layout = layout;
+        self.forgive_typos = forgive_typos;
+    }
+
     /// Starts a new training session.
     pub fn start(&mut self, set: Set, size: usize) {
         self.restart();
+
+        let (code, invalid) = match set.code(size) {
+            Ok(result) => result,
+            Err(err) => {
+                self.error = Some(err.to_string());
+                return;
+            }
+        };
         self.set = set;
         self.page = Page::Training;
 
-        let mut word_set = kana::build_set(
-            match set {
-                Set::Hiragana => kana::SET_HIRAGANA,
-                Set::Katakana => kana::SET_KATAKANA,
-                Set::All => kana::SET_ALL,
-                Set::Rare => kana::SET_ALL_RARE,
-            },
-            size,
-        );
-        word_set.shuffle();
+        let word_set = match &self.words {
+            Some(words) => code.build_from(words.as_ref()),
+            None => code.build(),
+        };
+        self.code = code.to_string();
 
         self.missing = word_set.missing.clone();
+        self.invalid = invalid;
+
+        // None of the requested characters can be trained, so there
+        // is nothing to do but report them.
+        if word_set.words.is_empty() {
+            self.page = Page::Summary;
+            self.errors = HashMap::new();
+            self.typos = HashMap::new();
+            self.times = Vec::new();
+            return;
+        }
 
         self.remaining = word_set.words.len();
         for it in word_set.words.iter() {
-            self.chars_total += it.word.chars().count();
+            self.chars_total += it.kana().chars().count();
         }
 
         self.word_index = 0;
         self.word_set = word_set;
-        self.word = String::from(self.word_set.words[0].word);
+        self.set_word();
 
         self.errors = HashMap::new();
+        self.typos = HashMap::new();
         self.word_time = self.word_set.words.iter().map(|_x| 0).collect();
     }
 
@@ -152,36 +280,48 @@ impl Model {
         }
 
         self.answer_time += elapsed_ms;
+        self.answered = None;
+        self.glosses = Vec::new();
 
         let num_words = self.word_set.words.len();
         if self.word_index < num_words {
-            let word = self.word_set.words[self.word_index];
-            let s = kana::Match::new(word.word, text);
+            let word = self.word_set.words[self.word_index].clone();
+            let mut s = kana::Match::new(word.kana(), text);
+            s.classify(self.layout);
 
             self.word_time[self.word_index] += elapsed_ms;
+            self.glosses = self.glosses(&word);
+
+            // Compute the failed syllables. Typos are counted apart, so
+            // that the errors only reflect the kana not recognized.
+            for chr in &s.chars {
+                let counts = match chr.kind {
+                    Some(kana::ErrorKind::Typo) => &mut self.typos,
+                    Some(kana::ErrorKind::Recognition) => &mut self.errors,
+                    None => continue,
+                };
+                counts.entry(chr.kana).and_modify(|x| *x += 1).or_insert(1);
+            }
 
-            if s.is_match {
+            if s.is_match || (self.forgive_typos && s.is_typo) {
+                self.answered = Some(word.clone());
                 self.hits += 1;
                 self.word_index += 1;
                 self.remaining -= 1;
-                self.chars_done += word.word.chars().count();
+                self.chars_done += word.kana().chars().count();
             } else {
                 self.misses += 1;
 
-                // Compute the failed syllables
-                for chr in &s.fails {
-                    self.errors.entry(*chr).and_modify(|x| *x += 1).or_insert(1);
-                }
-
                 // Move the word to later in the set.
-                let new_index = self.word_set.swap_current(self.word_index);
+                let mut rng = rand::thread_rng();
+                let new_index = self.word_set.swap_current(&mut rng, self.word_index);
                 self.word_time.swap(self.word_index, new_index);
             }
 
             self.submitted = Some(s);
 
             if self.word_index < num_words {
-                self.word = String::from(self.word_set.words[self.word_index].word);
+                self.set_word();
             } else {
                 self.page = Page::Summary;
                 self.compute_averages();
@@ -192,8 +332,13 @@ impl Model {
     /// Restarts the `Model` to the initial state.
     pub fn restart(&mut self) {
         self.page = Page::Start;
+        self.code = String::new();
+        self.error = None;
         self.word = String::new();
+        self.kanji = None;
+        self.generated = false;
         self.missing = Vec::new();
+        self.invalid = Vec::new();
         self.hits = 0;
         self.misses = 0;
         self.remaining = 0;
@@ -201,10 +346,36 @@ impl Model {
         self.chars_total = 0;
         self.answer_time = 0;
         self.submitted = None;
+        self.answered = None;
+        self.glosses = Vec::new();
         self.word_set = Default::default();
         self.word_index = 0;
     }
 
+    /// Returns the English meanings for a word. The dictionary is
+    /// preferred, since it has the full list of meanings.
+    fn glosses(&self, word: &kana::Word) -> Vec<String> {
+        if word.generated {
+            return Vec::new();
+        }
+        let glosses = match &self.dict {
+            Some(dict) => dict.glosses(&word.word, word.kana()),
+            None => Vec::new(),
+        };
+        match &word.gloss {
+            Some(gloss) if glosses.is_empty() => vec![gloss.clone()],
+            _ => glosses,
+        }
+    }
+
+    /// Sets `word` to the current word in the training set.
+    fn set_word(&mut self) {
+        let word = &self.word_set.words[self.word_index];
+        self.word = word.kana().to_string();
+        self.kanji = word.reading.as_ref().map(|_| word.word.clone());
+        self.generated = word.generated;
+    }
+
     fn compute_averages(&mut self) {
         // Calculate the total time for all words. We use that
         // to calculate the error.
@@ -218,7 +389,7 @@ impl Model {
         let mut chr_avg: HashMap<char, f64> = HashMap::new(); // Char average
         for (i, t) in self.word_time.iter().enumerate() {
             let word_time = (*t as f64) / 1000.0;
-            let word_str = self.word_set.words[i].word;
+            let word_str = self.word_set.words[i].kana();
             let word_len = word_str.chars().count() as f64;
             let word_avg = word_time / word_len;
             println!("--> {} ({:.03}s)", word_str, word_time);
@@ -261,7 +432,7 @@ impl Model {
         for _ in 0..100 {
             for (i, &t) in self.word_time.iter().enumerate() {
                 let word_time = (t as f64) / 1000.0;
-                let word_str = self.word_set.words[i].word;
+                let word_str = self.word_set.words[i].kana();
                 let word_weight = word_str.chars().map(|ref chr| iter_avg[chr]).sum::<f64>();
 
                 let mut chars: Vec<char> = word_str.chars().collect();
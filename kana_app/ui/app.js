@@ -13,10 +13,14 @@
             model: {
                 set: '',
                 page: '',
+                code: '',
+                error: null,
 
                 // Training:
 
                 word: '',
+                kanji: null,
+                generated: false,
                 hits: 0,
                 misses: 0,
                 remaining: 0,
@@ -27,8 +31,21 @@
                 // Diff for last submitted word.
                 submitted: null,
 
+                // Last word answered correctly.
+                answered: null,
+
+                // English meanings for the last submitted word.
+                glosses: [],
+
                 // Map of error counts for each kana character.
                 errors: {},
+
+                // Map of typo counts for each kana character.
+                typos: {},
+
+                // Options to detect typos.
+                layout: 'Qwerty',
+                forgive_typos: false,
             },
 
             paused: false,
@@ -99,19 +116,11 @@
             },
 
             error_table: function() {
-                let errs = this.model.errors || {};
-                let table = [];
-                for (let k in errs) {
-                    table.push({ kana: k, count: errs[k] });
-                }
-                table.sort(function(a, b) {
-                    if (a.count != b.count) {
-                        return b.count - a.count;
-                    } else {
-                        return a.kana.localeCompare(b.kana);
-                    }
-                });
-                return table;
+                return count_table(this.model.errors);
+            },
+
+            typo_table: function() {
+                return count_table(this.model.typos);
             }
         },
 
@@ -122,17 +131,29 @@
             '    <start-menu ',
             '        v-show="model.page == \'Start\'" ',
             '        @selected="start" ',
+            '        @options="options" ',
             '        v-model="model.set" ',
+            '        :error="model.error" ',
+            '        :layout="model.layout" ',
+            '        :forgive_typos="model.forgive_typos" ',
             '    />',
             '    <wrong-answer ',
             '        v-show="fail && !paused" ',
             '        :model="model.submitted" ',
+            '        :glosses="model.glosses" ',
+            '    />',
+            '    <word-meaning ',
+            '        v-show="model.answered && !paused" ',
+            '        :word="model.answered" ',
+            '        :glosses="model.glosses" ',
             '    />',
             '    <training-card ref="training" ',
             '        v-show="model.page == \'Training\' && !paused" ',
             '        @submit="submit" ',
             '        @restart="restart" ',
             '        :word="model.word" ',
+            '        :kanji="model.kanji" ',
+            '        :generated="model.generated" ',
             '        :hits="model.hits" ',
             '        :misses="model.misses" ',
             '        :remaining="model.remaining" ',
@@ -180,6 +201,12 @@
             '                    <b class="japanese">{{it.kana}}</b> {{it.count}}',
             '                </span>',
             '            </p>',
+            '            <p  v-if="typo_table.length">',
+            '                <label class="table">Typos:</label>',
+            '                <span v-for="it in typo_table" class="table">',
+            '                    <b class="japanese">{{it.kana}}</b> {{it.count}}',
+            '                </span>',
+            '            </p>',
             '            <p>',
             '                <label class="table">Estimated time per character:</label>',
             '                <span v-for="it in average_table" class="table">',
@@ -188,13 +215,24 @@
             '            </p>',
             '            <hr/>',
             '            <p style="font-size: 0.6em">',
-            '                Missing from set:',
+            '                Set code: <b class="mono">{{model.code}}</b>',
+            '            </p>',
+            '            <p style="font-size: 0.6em" v-if="model.missing.length">',
+            '                Unreachable characters (not found in any word):',
             '            </p>',
             '            <p style="font-size: 0.6em; padding: 0 5vw 0 5vw; text-align: center">',
             '                <span class="japanese" v-for="it in model.missing">',
             '                    {{it}}',
             '                </span>',
             '            </p>',
+            '            <p style="font-size: 0.6em" v-if="model.invalid.length">',
+            '                Invalid characters (not kana):',
+            '            </p>',
+            '            <p style="font-size: 0.6em; padding: 0 5vw 0 5vw; text-align: center">',
+            '                <span class="japanese" v-for="it in model.invalid">',
+            '                    {{it}}',
+            '                </span>',
+            '            </p>',
             '        </div>',
             '        <a href="#" class="restart" v-on:click.stop.prevent="restart">[Back to Menu]</a>',
             '    </div>',
@@ -202,15 +240,25 @@
         ].join('\n'),
 
         methods: {
-            start: function(size) {
+            start: function(size, text) {
+                let set = this.model.set;
+                if (set === 'Custom' || set.Custom) {
+                    set = { Custom: Array.from(text || '') };
+                } else if (set === 'Code' || set.Code) {
+                    set = { Code: text || '' };
+                }
                 this.reset_timer();
-                main.send({ Start: { set: this.model.set, size: size }});
+                main.send({ Start: { set: set, size: size }});
             },
 
             restart: function() {
                 main.send({ Restart: null });
             },
 
+            options: function(layout, forgive_typos) {
+                main.send({ Options: { layout: layout, forgive_typos: forgive_typos } });
+            },
+
             submit: function(text) {
                 let delta = this.get_timer();
                 this.reset_timer();
@@ -266,7 +314,7 @@
     function initComponents() {
 
         Vue.component('start-menu', {
-            props:['value'],
+            props:['value', 'error', 'layout', 'forgive_typos'],
             data: function() {
                 return {
                     options: [
@@ -274,22 +322,63 @@
                         { set: 'Katakana', text: 'Katakana' },
                         { set: 'All',      text: 'Hiragana + Katakana' },
                         { set: 'Rare',     text: 'Hiragana + Katakana + Rare' },
+                        { set: 'Custom',   text: 'Custom characters' },
+                        { set: 'Code',     text: 'Shared set code' },
                     ],
+                    layouts: ['Qwerty', 'Azerty', 'Dvorak'],
+                    custom: '',
+                    code: '',
                 };
             },
+            computed: {
+                // The model value is an object for `Custom` and `Code`.
+                selected: function() {
+                    let value = this.value || '';
+                    return value.Custom ? 'Custom' : value.Code ? 'Code' : value;
+                },
+                // Text input for the selected set.
+                text: function() {
+                    return this.selected == 'Code' ? this.code : this.custom;
+                },
+            },
+            watch: {
+                value: function(value) {
+                    if (value && value.Custom && !this.custom) {
+                        this.custom = value.Custom.join('');
+                    }
+                    if (value && value.Code && !this.code) {
+                        this.code = value.Code;
+                    }
+                },
+            },
             template: [
-                '<div @change="$emit(\'input\', $event.target.value)" class="start-menu">',
+                '<div class="start-menu">',
                 '    <h1>Choose your training</h1>',
-                '    <div class="menu-row" v-for="it in options">',
-                '        <input type="radio" :key="it.set" :id="it.set" :value="it.set" :checked="value == it.set" />',
+                '    <div class="menu-row" v-for="it in options" @change="$emit(\'input\', $event.target.value)">',
+                '        <input type="radio" :key="it.set" :id="it.set" :value="it.set" :checked="selected == it.set" />',
                 '        <label :for="it.set">{{it.text}}</label>',
                 '    </div>',
+                '    <div class="menu-row" v-show="selected == \'Custom\'">',
+                '        <input type="text" class="japanese" v-model="custom" placeholder="かきくけこ" />',
+                '    </div>',
+                '    <div class="menu-row" v-show="selected == \'Code\'">',
+                '        <input type="text" class="mono" v-model="code" />',
+                '    </div>',
+                '    <div class="menu-row options">',
+                '        <select :value="layout" @change="$emit(\'options\', $event.target.value, forgive_typos)">',
+                '            <option v-for="it in layouts" :key="it" :value="it">{{it}}</option>',
+                '        </select>',
+                '        <input type="checkbox" id="forgive_typos" :checked="forgive_typos" ',
+                '            @change="$emit(\'options\', layout, $event.target.checked)" />',
+                '        <label for="forgive_typos">Forgive typos</label>',
+                '    </div>',
+                '    <p class="error" v-show="error">{{error}}</p>',
                 '    <div class="button-row">',
-                '        <a href="#" class="button" @click="$emit(\'selected\',  50)">50</a>',
-                '        <a href="#" class="button" @click="$emit(\'selected\', 100)">100</a>',
-                '        <a href="#" class="button" @click="$emit(\'selected\', 150)">150</a>',
-                '        <a href="#" class="button" @click="$emit(\'selected\', 300)">250</a>',
-                '        <a href="#" class="button" @click="$emit(\'selected\',   0)">All</a>',
+                '        <a href="#" class="button" @click="$emit(\'selected\',  50, text)">50</a>',
+                '        <a href="#" class="button" @click="$emit(\'selected\', 100, text)">100</a>',
+                '        <a href="#" class="button" @click="$emit(\'selected\', 150, text)">150</a>',
+                '        <a href="#" class="button" @click="$emit(\'selected\', 300, text)">250</a>',
+                '        <a href="#" class="button" @click="$emit(\'selected\',   0, text)">All</a>',
                 '    </div>',
                 '</div>'
             ].join('\n'),
@@ -298,6 +387,7 @@
         Vue.component('wrong-answer', {
             props: [
                 'model',
+                'glosses',
             ],
             computed: {
                 data: function() {
@@ -305,34 +395,36 @@
                 },
                 diff: function() {
                     let model = this.model;
-                    if (!model || !model.diff) {
+                    if (!model || !model.chars) {
                         return {};
                     }
 
                     let kana = '';
                     let actual = '';
                     let answer = '';
-                    let kana_index = 0;
-                    for (let i = 0; i < model.diff.length; i++) {
-                        let it = model.diff[i];
-                        if (it.Same) {
-                            kana   += model.split[kana_index];
-                            actual += eq(it.Same);
-                            answer += eq(it.Same);
-                            kana_index++;
-                        } else if (it.Delete) {
-                            answer += del(it.Delete);
-                        } else if (it.Insert) {
-                            kana   += ins(model.split[kana_index]);
-                            actual += ins(it.Insert);
-                            kana_index++;
-                        } else if (it.Change) {
-                            let src = it.Change[0];
-                            let dst = it.Change[1];
-                            kana   += rep(model.split[kana_index]);
-                            actual += rep(dst);
-                            answer += rep(src);
-                            kana_index++;
+                    for (let i = 0; i < model.chars.length; i++) {
+                        let it = model.chars[i];
+                        if (it.before) {
+                            answer += del(it.before);
+                        }
+                        if (it.status === 'Correct') {
+                            kana   += it.kana;
+                            actual += eq(it.text);
+                            answer += eq(it.text);
+                        } else if (it.status === 'Extra') {
+                            kana   += rep(it.kana);
+                            actual += eq(it.text);
+                            answer += eq(it.text);
+                        } else if (it.status === 'Missing') {
+                            kana   += ins(it.kana);
+                            actual += ins(it.expected);
+                        } else if (it.status === 'Wrong') {
+                            kana   += rep(it.kana);
+                            actual += rep(it.expected);
+                            answer += rep(it.text);
+                        }
+                        if (it.after) {
+                            answer += del(it.after);
                         }
                     }
 
@@ -357,6 +449,42 @@
                     function rep(txt) {
                         return '<span class="diff-rep">' + txt + '</span>'
                     }
+                },
+                // Explanation for each labeled mistake in the diff.
+                feedback: function() {
+                    let model = this.model;
+                    if (!model || !model.chars || !model.mistakes) {
+                        return [];
+                    }
+
+                    let lines = [];
+                    for (let i = 0; i < model.chars.length; i++) {
+                        let it = model.chars[i];
+                        for (let j = it.diff.start; j < it.diff.end; j++) {
+                            let mistake = model.mistakes[j];
+                            let text = mistake && explain(it.kana, mistake);
+                            if (text && lines.indexOf(text) < 0) {
+                                lines.push(text);
+                            }
+                        }
+                    }
+                    return lines;
+
+                    function explain(kana, mistake) {
+                        if (mistake === 'MissedSokuon') {
+                            return 'The small ' + kana + ' doubles the next consonant.';
+                        } else if (mistake === 'MissedLongVowel') {
+                            return 'The ' + kana + ' makes the vowel before it long.';
+                        } else if (mistake === 'SplitYoon') {
+                            return 'A small ゃ, ゅ or ょ forms a single syllable with the kana before it.';
+                        } else if (mistake.Dakuten) {
+                            return kana + ' was read as ' + mistake.Dakuten + ', check the dakuten.';
+                        } else if (mistake.LookAlike) {
+                            return kana + ' was read as ' + mistake.LookAlike + ', which looks alike.';
+                        } else if (mistake.WrongScript) {
+                            return kana + ' was read as ' + mistake.WrongScript + ', from the other script.';
+                        }
+                    }
                 }
             },
             template: [
@@ -364,6 +492,31 @@
                 '    <p><b>word:</b> <span class="japanese" v-html="diff.kana"></span></p>',
                 '    <p><b>expected:</b> <span class="mono" v-html="diff.actual"></span></p>',
                 '    <p><b>was:</b> <span class="mono" v-html="diff.answer"></span></p>',
+                '    <p class="typo" v-if="data.is_typo">(looks like a typo)</p>',
+                '    <p class="feedback" v-for="it in feedback">{{it}}</p>',
+                '    <p class="meaning" v-if="glosses && glosses.length">{{glosses.join(\'; \')}}</p>',
+                '</div>',
+            ].join('\n'),
+        });
+
+        Vue.component('word-meaning', {
+            props: [
+                'word',
+                'glosses',
+            ],
+            computed: {
+                data: function() {
+                    return this.word || {};
+                },
+            },
+            template: [
+                '<div class="word-meaning" v-show="glosses && glosses.length">',
+                '    <p>',
+                '        <b class="japanese">{{data.word}}</b>',
+                '        <span class="japanese" v-if="data.reading">({{data.reading}})</span>',
+                '        <em v-if="data.pos">{{data.pos}}</em>',
+                '    </p>',
+                '    <p>{{glosses.join(\'; \')}}</p>',
                 '</div>',
             ].join('\n'),
         });
@@ -371,6 +524,8 @@
         Vue.component('training-card', {
             props: [
                 'word',
+                'kanji',
+                'generated',
                 'hits',
                 'misses',
                 'remaining',
@@ -418,7 +573,9 @@
 
             template: [
                 '<div class="training-card">',
+                '    <p class="kanji japanese" v-show="kanji">{{kanji}}</p>',
                 '    <p class="word japanese">{{word}}</p>',
+                '    <p class="generated" v-show="generated">(pseudo-word)</p>',
                 '    <input ref="input" type="text" v-model="text" v-on:keyup.enter="submit"/>',
                 '    <p class="status">{{status}}</p>',
                 '    <div class="progress">',
@@ -460,4 +617,21 @@
         window.location.reload();
     }
 
+    // Returns a table of `{ kana, count }` from a map of counts for each
+    // kana character, sorted by the most frequent.
+    function count_table(counts) {
+        let table = [];
+        for (let k in counts || {}) {
+            table.push({ kana: k, count: counts[k] });
+        }
+        table.sort(function(a, b) {
+            if (a.count != b.count) {
+                return b.count - a.count;
+            } else {
+                return a.kana.localeCompare(b.kana);
+            }
+        });
+        return table;
+    }
+
 }());